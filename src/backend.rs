@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::profile::{Profiles, PROFILES_BACKEND};
+use crate::{DetectorConfig, LangInfo, Method, WhatLangError};
+
+/// The name of the backend that wraps whatlang, used unless another backend is configured.
+pub const DEFAULT_BACKEND: &str = "whatlang";
+
+/// A language detection backend that [`LangDetector`](crate::LangDetector) dispatches through.
+///
+/// Preprocessing and thresholds are applied by the detector, so a backend only detects the
+/// language of the text it is given.
+pub trait LanguageDetector: fmt::Debug + Send + Sync {
+    /// Returns the detected language of a text, or `None` if it can't be detected.
+    fn detect(&self, text: &str) -> Option<LangInfo>;
+}
+
+/// Builds a backend from the settings of a detector, e.g. its method or allowlist.
+pub type BackendFactory =
+    dyn Fn(&DetectorConfig) -> Result<Arc<dyn LanguageDetector>, WhatLangError> + Send + Sync;
+
+static BACKENDS: LazyLock<RwLock<BTreeMap<String, Arc<BackendFactory>>>> = LazyLock::new(|| {
+    let whatlang: Arc<BackendFactory> = Arc::new(|config| Ok(Arc::new(Whatlang::new(config)?)));
+    let profiles: Arc<BackendFactory> =
+        Arc::new(|config| Ok(Arc::new(Profiles::standalone(config)?)));
+    let mut backends = BTreeMap::from([
+        (DEFAULT_BACKEND.to_string(), whatlang),
+        (PROFILES_BACKEND.to_string(), profiles),
+    ]);
+    // every method on its own, so that the methods can vote in an ensemble
+    for method in [Method::Combined, Method::Trigram, Method::Alphabet] {
+        let factory: Arc<BackendFactory> = Arc::new(move |config: &DetectorConfig| {
+            Ok(Arc::new(Whatlang::new(&config.clone().method(method))?))
+        });
+        backends.insert(method_backend(method), factory);
+    }
+    RwLock::new(backends)
+});
+
+/// Returns the name of the backend that detects with whatlang's `method` regardless of the method
+/// of the detector, e.g. `whatlang-trigram`.
+fn method_backend(method: Method) -> String {
+    format!("{}-{}", DEFAULT_BACKEND, method.name())
+}
+
+/// Registers a backend under `name`, so that detectors configured with
+/// [`DetectorConfig::backend`] and the `--backend` option can select it. A backend registered
+/// under the name of another one replaces it.
+pub fn register_backend(
+    name: &str,
+    factory: impl Fn(&DetectorConfig) -> Result<Arc<dyn LanguageDetector>, WhatLangError>
+        + Send
+        + Sync
+        + 'static,
+) {
+    BACKENDS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), Arc::new(factory));
+}
+
+/// Returns the names of all registered backends in alphabetical order.
+pub fn backends() -> Vec<String> {
+    BACKENDS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Builds the backend registered under `name`.
+pub(crate) fn build_backend(
+    name: &str,
+    config: &DetectorConfig,
+) -> Result<Arc<dyn LanguageDetector>, WhatLangError> {
+    // the lock is released before the factory runs, so that factories may register backends
+    let factory = BACKENDS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned();
+    match factory {
+        Some(factory) => factory(config),
+        None => Err(WhatLangError::InvalidConfig {
+            message: format!(
+                "Unknown backend '{}', expected one of: {}",
+                name,
+                backends().join(", ")
+            ),
+        }),
+    }
+}
+
+/// The default backend, detecting with whatlang's method and filter list.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Whatlang {
+    options: whatlang::dev::Options,
+}
+
+impl Whatlang {
+    pub(crate) fn new(config: &DetectorConfig) -> Result<Whatlang, WhatLangError> {
+        // with trained profiles, the codes that whatlang doesn't know are left to the profiles
+        let custom = !config.profiles.is_empty();
+        let filter_list = if !config.allowlist.is_empty() {
+            whatlang::dev::FilterList::Allow(parse_langs(&config.allowlist, custom)?)
+        } else if !config.denylist.is_empty() {
+            whatlang::dev::FilterList::Deny(parse_langs(&config.denylist, custom)?)
+        } else {
+            whatlang::dev::FilterList::All
+        };
+        Ok(Whatlang {
+            options: config.method.options().set_filter_list(filter_list),
+        })
+    }
+}
+
+impl LanguageDetector for Whatlang {
+    fn detect(&self, text: &str) -> Option<LangInfo> {
+        whatlang::dev::detect_with_options(text, &self.options).map(LangInfo::from_info)
+    }
+}
+
+/// Parses ISO 639-3 codes, skipping unknown codes if they may be `custom` codes of profiles.
+fn parse_langs(codes: &[String], custom: bool) -> Result<Vec<whatlang::Lang>, WhatLangError> {
+    codes
+        .iter()
+        .filter_map(
+            |code| match whatlang::Lang::from_code(code.to_lowercase()) {
+                Some(lang) => Some(Ok(lang)),
+                None if custom => None,
+                None => Some(Err(WhatLangError::InvalidConfig {
+                    message: format!("Unknown language code '{}'", code),
+                })),
+            },
+        )
+        .collect()
+}
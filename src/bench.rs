@@ -0,0 +1,266 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+
+use crate::items::{for_each_item, ItemOptions};
+use crate::{LangDetector, Method, WhatLangError};
+
+/// How the corpus of the `bench` subcommand is measured.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// How often every text is detected
+    pub iterations: usize,
+    /// The upper bounds in characters of the text-length buckets, in ascending order. Longer
+    /// texts go into a last bucket without upper bound
+    pub buckets: Vec<usize>,
+    /// The methods of whatlang that are measured one after the other. If empty, the detector is
+    /// measured with its own method
+    pub methods: Vec<Method>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            iterations: 3,
+            buckets: vec![64, 256, 1024],
+            methods: vec![],
+        }
+    }
+}
+
+/// The throughput of the detector over the whole corpus and over every text-length bucket, per
+/// measured method.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct WhatLangBenchmark {
+    /// The number of texts in the corpus
+    texts: usize,
+    bytes: usize,
+    iterations: usize,
+    /// The number of items that were skipped due to errors
+    skipped: usize,
+    runs: Vec<WhatLangBenchmarkRun>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhatLangBenchmarkRun {
+    /// The method of whatlang the run detected with, unless the detector was measured with its
+    /// own method
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    method: Option<Method>,
+    /// The range of text lengths in characters, `all` for the whole corpus
+    bucket: String,
+    texts: usize,
+    bytes: usize,
+    seconds: f64,
+    texts_per_second: f64,
+    megabytes_per_second: f64,
+    /// Latency percentiles of a single detection in microseconds
+    latency_p50_us: f64,
+    latency_p90_us: f64,
+    latency_p99_us: f64,
+    latency_max_us: f64,
+}
+
+/// Detects every text of the corpus `iterations` times with the detector of `items` and measures
+/// the throughput and latency, for the whole corpus and per text-length bucket. With `methods`,
+/// the corpus is measured once per method of whatlang, with the detector's other settings.
+///
+/// The corpus is read like the items of the `split` and `filter` subcommands.
+pub fn benchmark(
+    inputs: Vec<PathBuf>,
+    items: &ItemOptions,
+    options: &BenchOptions,
+) -> Result<WhatLangBenchmark, WhatLangError> {
+    let mut texts: Vec<String> = vec![];
+    let mut skipped = 0;
+    for_each_item(&inputs, items.format, |item| {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                error!("{}. Skipping item", e);
+                skipped += 1;
+                return Ok(());
+            }
+        };
+        match items.text(item) {
+            Ok(text) => texts.push(text),
+            Err(e) => {
+                error!("Invalid item '{}': {}. Skipping item", item, e);
+                skipped += 1;
+            }
+        }
+        Ok(())
+    })?;
+    if texts.is_empty() {
+        return Err(WhatLangError::NoTexts);
+    }
+
+    let mut buckets = options.buckets.clone();
+    buckets.sort_unstable();
+    buckets.dedup();
+    let lengths: Vec<usize> = texts.iter().map(|text| text.chars().count()).collect();
+
+    let detectors: Vec<(Option<Method>, LangDetector)> = if options.methods.is_empty() {
+        vec![(None, items.detector.clone())]
+    } else {
+        options
+            .methods
+            .iter()
+            .map(|method| {
+                let config = items.detector.config().clone().method(*method);
+                Ok((Some(*method), config.build()?))
+            })
+            .collect::<Result<_, WhatLangError>>()?
+    };
+
+    let mut runs = vec![];
+    for (method, detector) in detectors {
+        debug!("Benchmarking {} texts with {:?}", texts.len(), method);
+        let latencies = measure(&texts, &detector, options.iterations);
+        let mut method_runs = vec![run("all".to_string(), &texts, &latencies, |_| true)];
+        for (i, upper) in buckets.iter().enumerate() {
+            let lower = if i == 0 { 0 } else { buckets[i - 1] + 1 };
+            let label = format!("{}-{}", lower, upper);
+            method_runs.push(run(label, &texts, &latencies, |t| {
+                (lower..=*upper).contains(&lengths[t])
+            }));
+        }
+        if let Some(last) = buckets.last() {
+            let label = format!("{}+", last + 1);
+            method_runs.push(run(label, &texts, &latencies, |t| lengths[t] > *last));
+        }
+        runs.extend(
+            method_runs
+                .into_iter()
+                .filter(|run| run.texts > 0)
+                .map(|run| WhatLangBenchmarkRun { method, ..run }),
+        );
+    }
+
+    Ok(WhatLangBenchmark {
+        texts: texts.len(),
+        bytes: texts.iter().map(String::len).sum(),
+        iterations: options.iterations,
+        skipped,
+        runs,
+    })
+}
+
+/// Returns the latencies of every detection of every text, indexed by text.
+fn measure(texts: &[String], detector: &LangDetector, iterations: usize) -> Vec<Vec<Duration>> {
+    // one untimed pass so that the first iteration doesn't pay for cold caches
+    for text in texts {
+        detector.detect(text);
+    }
+    let mut latencies = vec![Vec::with_capacity(iterations); texts.len()];
+    for _ in 0..iterations {
+        for (text, latencies) in texts.iter().zip(latencies.iter_mut()) {
+            let start = Instant::now();
+            std::hint::black_box(detector.detect(std::hint::black_box(text)));
+            latencies.push(start.elapsed());
+        }
+    }
+    latencies
+}
+
+fn run(
+    bucket: String,
+    texts: &[String],
+    latencies: &[Vec<Duration>],
+    selected: impl Fn(usize) -> bool,
+) -> WhatLangBenchmarkRun {
+    let indices: Vec<usize> = (0..texts.len()).filter(|t| selected(*t)).collect();
+    let bytes: usize = indices.iter().map(|t| texts[*t].len()).sum();
+    let mut durations: Vec<Duration> = indices
+        .iter()
+        .flat_map(|t| latencies[*t].iter().copied())
+        .collect();
+    durations.sort_unstable();
+    let seconds = durations.iter().sum::<Duration>().as_secs_f64();
+    let detections = durations.len();
+    let iterations = detections / indices.len().max(1);
+
+    WhatLangBenchmarkRun {
+        method: None,
+        bucket,
+        texts: indices.len(),
+        bytes,
+        seconds,
+        texts_per_second: per_second(detections as f64, seconds),
+        megabytes_per_second: per_second((bytes * iterations) as f64 / 1_000_000.0, seconds),
+        latency_p50_us: percentile(&durations, 0.5),
+        latency_p90_us: percentile(&durations, 0.9),
+        latency_p99_us: percentile(&durations, 0.99),
+        latency_max_us: percentile(&durations, 1.0),
+    }
+}
+
+fn per_second(amount: f64, seconds: f64) -> f64 {
+    if seconds == 0.0 {
+        0.0
+    } else {
+        amount / seconds
+    }
+}
+
+/// Returns the nearest-rank percentile of sorted durations in microseconds.
+fn percentile(sorted: &[Duration], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1].as_secs_f64() * 1_000_000.0
+}
+
+/// Formats the benchmark as a plain text table with one row per bucket and method.
+impl fmt::Display for WhatLangBenchmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "texts: {}, bytes: {}, iterations: {}, skipped: {}",
+            self.texts, self.bytes, self.iterations, self.skipped
+        )?;
+        writeln!(f)?;
+        // the method column is only shown if methods were compared
+        let methods = self.runs.iter().any(|run| run.method.is_some());
+        let method = |name: &str| {
+            if methods {
+                format!("{:>8}  ", name)
+            } else {
+                String::new()
+            }
+        };
+        writeln!(
+            f,
+            "{}{:>10}  {:>6}  {:>12}  {:>8}  {:>9}  {:>9}  {:>9}  {:>9}",
+            method("method"),
+            "bucket",
+            "texts",
+            "texts/sec",
+            "MB/sec",
+            "p50 µs",
+            "p90 µs",
+            "p99 µs",
+            "max µs"
+        )?;
+        for run in &self.runs {
+            writeln!(
+                f,
+                "{}{:>10}  {:>6}  {:>12.1}  {:>8.2}  {:>9.1}  {:>9.1}  {:>9.1}  {:>9.1}",
+                method(run.method.map_or("", |method| method.name())),
+                run.bucket,
+                run.texts,
+                run.texts_per_second,
+                run.megabytes_per_second,
+                run.latency_p50_us,
+                run.latency_p90_us,
+                run.latency_p99_us,
+                run.latency_max_us
+            )?;
+        }
+        Ok(())
+    }
+}
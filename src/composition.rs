@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The characters of a text by script, and its digits, punctuation and emoji.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScriptComposition {
+    /// The number of characters of the text, including whitespace
+    characters: usize,
+    /// The letters of every script, most frequent first
+    scripts: Vec<ScriptShare>,
+    digits: usize,
+    punctuation: usize,
+    /// The number of emoji, each of which may consist of several characters
+    emoji: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScriptShare {
+    script: String,
+    characters: usize,
+    /// The share of the script among the letters of all scripts
+    ratio: f64,
+}
+
+impl ScriptComposition {
+    pub fn characters(&self) -> usize {
+        self.characters
+    }
+
+    pub fn scripts(&self) -> &[ScriptShare] {
+        &self.scripts
+    }
+
+    pub fn digits(&self) -> usize {
+        self.digits
+    }
+
+    pub fn punctuation(&self) -> usize {
+        self.punctuation
+    }
+
+    pub fn emoji(&self) -> usize {
+        self.emoji
+    }
+
+    /// Returns the composition of two texts joined together.
+    pub(crate) fn merge(&self, other: &ScriptComposition) -> ScriptComposition {
+        let mut scripts: Vec<(String, usize)> = self
+            .scripts
+            .iter()
+            .map(|share| (share.script.clone(), share.characters))
+            .collect();
+        for share in &other.scripts {
+            match scripts
+                .iter_mut()
+                .find(|(script, _)| *script == share.script)
+            {
+                Some((_, characters)) => *characters += share.characters,
+                None => scripts.push((share.script.clone(), share.characters)),
+            }
+        }
+        ScriptComposition {
+            characters: self.characters + other.characters,
+            scripts: shares(scripts),
+            digits: self.digits + other.digits,
+            punctuation: self.punctuation + other.punctuation,
+            emoji: self.emoji + other.emoji,
+        }
+    }
+}
+
+impl ScriptShare {
+    /// Returns the name of the script, e.g. `Latin`.
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    pub fn characters(&self) -> usize {
+        self.characters
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+}
+
+/// Counts the letters of every script that whatlang knows and the digits, punctuation and emoji
+/// of a text, e.g. to find Latin brand names in a Cyrillic text.
+pub fn script_composition(text: &str) -> ScriptComposition {
+    let mut composition = ScriptComposition {
+        characters: text.chars().count(),
+        ..ScriptComposition::default()
+    };
+    for grapheme in text.graphemes(true) {
+        if is_emoji(grapheme) {
+            composition.emoji += 1;
+            continue;
+        }
+        for c in grapheme.chars() {
+            if c.is_numeric() {
+                composition.digits += 1;
+            } else if is_punctuation(c) {
+                composition.punctuation += 1;
+            }
+        }
+    }
+
+    let mut scripts: Vec<(whatlang::Script, usize)> = vec![];
+    let mut buffer = [0; 4];
+    for c in text.chars() {
+        // the script of a single character as whatlang classifies it, none for digits and spaces
+        let Some(script) = whatlang::detect_script(c.encode_utf8(&mut buffer)) else {
+            continue;
+        };
+        match scripts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, count)) => *count += 1,
+            None => scripts.push((script, 1)),
+        }
+    }
+    composition.scripts = shares(
+        scripts
+            .into_iter()
+            .map(|(script, count)| (script.to_string(), count))
+            .collect(),
+    );
+    composition
+}
+
+/// Returns whether a grapheme is an emoji. Emoji with skin tones, flags and ZWJ sequences are
+/// single graphemes.
+pub(crate) fn is_emoji(grapheme: &str) -> bool {
+    emojis::get(grapheme).is_some()
+}
+
+/// Sorts the letter counts of the scripts by frequency and computes their ratios.
+fn shares(mut scripts: Vec<(String, usize)>) -> Vec<ScriptShare> {
+    scripts.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+    let total: usize = scripts.iter().map(|(_, count)| count).sum();
+    scripts
+        .into_iter()
+        .map(|(script, characters)| ScriptShare {
+            script,
+            characters,
+            ratio: characters as f64 / total as f64,
+        })
+        .collect()
+}
+
+/// Returns whether a character is ASCII punctuation or in one of the Unicode punctuation
+/// categories, e.g. the Arabic comma `،`, the Devanagari danda `।` or the ideographic full stop `。`.
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(
+            get_general_category(c),
+            GeneralCategory::ConnectorPunctuation
+                | GeneralCategory::DashPunctuation
+                | GeneralCategory::OpenPunctuation
+                | GeneralCategory::ClosePunctuation
+                | GeneralCategory::InitialPunctuation
+                | GeneralCategory::FinalPunctuation
+                | GeneralCategory::OtherPunctuation
+        )
+}
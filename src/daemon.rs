@@ -0,0 +1,191 @@
+use std::fs::{self, Permissions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, error, info, warn};
+use serde_json::{json, Value};
+
+use crate::{run_worker, LangDetector, ProcessRequest, WhatLangError};
+
+/// The file name of the socket in `$XDG_RUNTIME_DIR`, see [`default_socket`].
+const SOCKET_NAME: &str = "whatlang-cli.sock";
+
+/// How often the daemon checks whether it is shutting down.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the client waits for the daemon to accept a request or to answer it.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Returns the socket the daemon listens on and the client connects to by default:
+/// `whatlang-cli.sock` in `$XDG_RUNTIME_DIR`, which only its user can access. Returns `None` if
+/// `$XDG_RUNTIME_DIR` isn't set.
+pub fn default_socket() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| PathBuf::from(dir).join(SOCKET_NAME))
+}
+
+/// Listens on the Unix socket `socket` until `shutdown` is set. Every connection speaks the
+/// newline-delimited JSON-RPC protocol of [`run_worker`] and is served on its own thread.
+///
+/// Only the user of the daemon may connect: the socket file is only readable and writable by
+/// them, and connections of processes of other users are closed. A stale socket file left behind
+/// by a daemon that didn't shut down cleanly is replaced. The socket file is removed on shutdown.
+pub fn run_daemon(socket: &Path, shutdown: Arc<AtomicBool>) -> Result<(), WhatLangError> {
+    let listen_failed = |source| WhatLangError::Listen {
+        address: format!("{:?}", socket),
+        source,
+    };
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(listen_failed(io::Error::new(
+                ErrorKind::AddrInUse,
+                "A daemon is already listening",
+            )));
+        }
+        debug!("Removing stale socket {:?}", socket);
+        fs::remove_file(socket).map_err(listen_failed)?;
+    }
+    let listener = UnixListener::bind(socket).map_err(listen_failed)?;
+    fs::set_permissions(socket, Permissions::from_mode(0o600)).map_err(listen_failed)?;
+    listener.set_nonblocking(true).map_err(listen_failed)?;
+    info!("Listening on {:?}", socket);
+
+    while !shutdown.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => match accept(&stream) {
+                Ok(()) => {
+                    thread::spawn(move || serve_connection(stream));
+                }
+                Err(e) => error!("Failed to accept connection: {}", e),
+            },
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => error!("Failed to accept connection: {}", e),
+        }
+    }
+
+    fs::remove_file(socket).map_err(WhatLangError::write_failed(Some(socket)))?;
+    info!("Shut down");
+    Ok(())
+}
+
+/// Checks that the peer of a connection runs as the user of the daemon, since `process`
+/// requests open the files they name.
+fn accept(stream: &UnixStream) -> io::Result<()> {
+    let peer = peer_uid(stream)?;
+    let user = nix::unistd::geteuid().as_raw();
+    if peer.is_some_and(|peer| peer != user) {
+        warn!("Rejected connection of user {:?}", peer);
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            "The peer runs as another user",
+        ));
+    }
+    stream.set_nonblocking(false)
+}
+
+/// Returns the effective user id of the peer of a connection, or `None` on platforms where it
+/// can't be queried and only the permissions of the socket restrict who can connect.
+fn peer_uid(stream: &UnixStream) -> io::Result<Option<u32>> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let credentials =
+            nix::sys::socket::getsockopt(stream, nix::sys::socket::sockopt::PeerCredentials)?;
+        Ok(Some(credentials.uid()))
+    }
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    ))]
+    {
+        let (uid, _) = nix::unistd::getpeereid(stream)?;
+        Ok(Some(uid.as_raw()))
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    )))]
+    {
+        let _ = stream;
+        Ok(None)
+    }
+}
+
+fn serve_connection(stream: UnixStream) {
+    debug!("Accepted connection");
+    let result = stream
+        .try_clone()
+        .map_err(Into::into)
+        .and_then(|reader| run_worker(BufReader::new(reader), stream, LangDetector::default()));
+    if let Err(e) = result {
+        error!("Connection failed: {}", e);
+    }
+}
+
+/// Sends a detection run to the daemon listening on `socket` and returns its output, or `None` if
+/// no daemon is running.
+///
+/// If the run fails, the error has the code of the daemon's error, so that it is reported as if
+/// the run had failed in-process.
+pub fn request_daemon(
+    socket: &Path,
+    request: &ProcessRequest,
+) -> Result<Option<Value>, WhatLangError> {
+    let failed = |message: String| WhatLangError::Daemon {
+        code: "daemon".to_string(),
+        message,
+    };
+    let stream = match UnixStream::connect(socket) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            return Ok(None);
+        }
+        Err(e) => return Err(failed(format!("Can't connect to {:?}: {}", socket, e))),
+    };
+
+    let rpc = json!({"jsonrpc": "2.0", "id": 1, "method": "process", "params": request});
+    let mut line = serde_json::to_vec(&rpc)?;
+    line.push(b'\n');
+    let mut response = String::new();
+    stream
+        .set_read_timeout(Some(CLIENT_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+        .and_then(|_| (&stream).write_all(&line))
+        .and_then(|_| BufReader::new(&stream).read_line(&mut response))
+        .map_err(|e| failed(format!("No answer on {:?}: {}", socket, e)))?;
+
+    let mut response: Value =
+        serde_json::from_str(&response).map_err(|e| failed(format!("Invalid response: {}", e)))?;
+    if let Some(error) = response.get("error") {
+        return Err(WhatLangError::Daemon {
+            code: error["data"]["code"]
+                .as_str()
+                .unwrap_or("daemon")
+                .to_string(),
+            message: error["message"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_string(),
+        });
+    }
+    match response.get_mut("result") {
+        Some(result) => Ok(Some(result.take())),
+        None => Err(failed("Invalid response".to_string())),
+    }
+}
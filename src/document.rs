@@ -0,0 +1,272 @@
+use std::error::Error;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+/// The maximum uncompressed size of a single entry of a document, to guard against zip bombs.
+const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Zip based document formats whose text can be extracted directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DocumentKind {
+    Docx,
+    Odt,
+    Epub,
+}
+
+impl DocumentKind {
+    pub(crate) fn from_path(path: &Path) -> Option<DocumentKind> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "docx" => Some(DocumentKind::Docx),
+            "odt" => Some(DocumentKind::Odt),
+            "epub" => Some(DocumentKind::Epub),
+            _ => None,
+        }
+    }
+}
+
+/// A chapter or section of a document. Text in front of the first heading has no title.
+#[derive(Debug, Clone)]
+pub(crate) struct Section {
+    pub(crate) title: Option<String>,
+    pub(crate) text: String,
+}
+
+/// Extracts the text of a document, split into its chapters (EPUB) or sections starting at
+/// headings (DOCX, ODT).
+pub(crate) fn extract_sections(
+    kind: DocumentKind,
+    buffer: &[u8],
+) -> Result<Vec<Section>, Box<dyn Error>> {
+    let mut archive = ZipArchive::new(Cursor::new(buffer))?;
+    match kind {
+        DocumentKind::Docx => {
+            let xml = read_entry(&mut archive, "word/document.xml")?;
+            Ok(split_at_headings(paragraphs(&xml, kind)?))
+        }
+        DocumentKind::Odt => {
+            let xml = read_entry(&mut archive, "content.xml")?;
+            Ok(split_at_headings(paragraphs(&xml, kind)?))
+        }
+        DocumentKind::Epub => {
+            let mut sections = vec![];
+            for chapter in epub_spine(&mut archive)? {
+                let xml = read_entry(&mut archive, &chapter)?;
+                let paragraphs = paragraphs(&xml, kind)?;
+                let title = paragraphs
+                    .iter()
+                    .find(|p| p.heading)
+                    .map(|p| p.text.clone())
+                    .unwrap_or(chapter);
+                sections.push(Section {
+                    title: Some(title),
+                    text: join_paragraphs(&paragraphs),
+                });
+            }
+            Ok(sections)
+        }
+    }
+}
+
+/// Concatenates the text of all sections of a document.
+pub(crate) fn join_sections(sections: &[Section]) -> String {
+    sections
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[derive(Debug)]
+struct Paragraph {
+    text: String,
+    heading: bool,
+}
+
+fn read_entry(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut string = String::new();
+    archive
+        .by_name(name)
+        .map_err(|e| format!("Missing {} in document: {}", name, e))?
+        .take(MAX_ENTRY_SIZE + 1)
+        .read_to_string(&mut string)?;
+    if string.len() as u64 > MAX_ENTRY_SIZE {
+        return Err(format!("{} in document exceeds {} bytes", name, MAX_ENTRY_SIZE).into());
+    }
+    Ok(string)
+}
+
+/// Resolves the chapters of an EPUB in reading order via `META-INF/container.xml` and the spine
+/// of the OPF package document.
+fn epub_spine(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<Vec<String>, Box<dyn Error>> {
+    let container = read_entry(archive, "META-INF/container.xml")?;
+    let mut rootfile = None;
+    let mut reader = Reader::from_str(&container);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"rootfile" => {
+                rootfile = attribute(&e, b"full-path");
+                break;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let rootfile = rootfile.ok_or("Missing rootfile in EPUB container")?;
+    let base = match rootfile.rfind('/') {
+        Some(i) => &rootfile[..=i],
+        None => "",
+    };
+
+    let package = read_entry(archive, &rootfile)?;
+    let mut manifest: Vec<(String, String)> = vec![];
+    let mut spine: Vec<String> = vec![];
+    let mut reader = Reader::from_str(&package);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"item" => {
+                    if let (Some(id), Some(href)) = (attribute(&e, b"id"), attribute(&e, b"href")) {
+                        manifest.push((id, href));
+                    }
+                }
+                b"itemref" => spine.extend(attribute(&e, b"idref")),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(spine
+        .iter()
+        .filter_map(|idref| manifest.iter().find(|(id, _)| id == idref))
+        .map(|(_, href)| format!("{}{}", base, href))
+        .collect())
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// Collects the non-empty paragraphs of a DOCX `document.xml`, an ODT `content.xml` or an EPUB
+/// XHTML chapter.
+fn paragraphs(xml: &str, kind: DocumentKind) -> Result<Vec<Paragraph>, Box<dyn Error>> {
+    let mut reader = Reader::from_str(xml);
+    let mut paragraphs = vec![];
+    let mut current = Paragraph {
+        text: String::new(),
+        heading: false,
+    };
+    // DOCX keeps text in `w:t` runs, XHTML must skip `head`, `script` and `style`
+    let mut in_text = kind != DocumentKind::Docx;
+    let mut skip_depth = 0;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = e.local_name();
+                match (kind, name.as_ref()) {
+                    (DocumentKind::Docx, b"t") => in_text = true,
+                    (DocumentKind::Epub, b"head" | b"script" | b"style") => skip_depth += 1,
+                    (DocumentKind::Odt, b"h") | (DocumentKind::Epub, b"h1" | b"h2" | b"h3") => {
+                        finish_paragraph(&mut paragraphs, &mut current);
+                        current.heading = true;
+                    }
+                    (DocumentKind::Odt, b"p")
+                    | (DocumentKind::Epub, b"p" | b"div" | b"li" | b"tr" | b"h4" | b"h5" | b"h6") => {
+                        finish_paragraph(&mut paragraphs, &mut current)
+                    }
+                    _ => {}
+                }
+            }
+            Event::Empty(e) => match (kind, e.local_name().as_ref()) {
+                (DocumentKind::Docx, b"pStyle") => {
+                    current.heading = attribute(&e, b"val").is_some_and(|style| {
+                        style.starts_with("Heading") || style.starts_with("Title")
+                    })
+                }
+                (DocumentKind::Docx, b"tab" | b"br")
+                | (DocumentKind::Odt, b"s" | b"tab" | b"line-break")
+                | (DocumentKind::Epub, b"br") => current.text.push(' '),
+                _ => {}
+            },
+            Event::End(e) => match (kind, e.local_name().as_ref()) {
+                (DocumentKind::Docx, b"t") => in_text = false,
+                (DocumentKind::Docx, b"p") | (DocumentKind::Odt, b"p" | b"h") => {
+                    finish_paragraph(&mut paragraphs, &mut current)
+                }
+                (DocumentKind::Epub, b"head" | b"script" | b"style") => skip_depth -= 1,
+                (
+                    DocumentKind::Epub,
+                    b"p" | b"div" | b"li" | b"tr" | b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6",
+                ) => finish_paragraph(&mut paragraphs, &mut current),
+                _ => {}
+            },
+            Event::Text(t) if in_text && skip_depth == 0 => match t.unescape() {
+                Ok(text) => current.text.push_str(&text),
+                // XHTML may use named entities like `&nbsp;` that XML doesn't know
+                Err(_) => current.text.push_str(&String::from_utf8_lossy(&t)),
+            },
+            Event::CData(t) if in_text && skip_depth == 0 => {
+                current.text.push_str(&String::from_utf8_lossy(&t))
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    finish_paragraph(&mut paragraphs, &mut current);
+    Ok(paragraphs)
+}
+
+fn finish_paragraph(paragraphs: &mut Vec<Paragraph>, current: &mut Paragraph) {
+    let text = current
+        .text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !text.is_empty() {
+        paragraphs.push(Paragraph {
+            text,
+            heading: current.heading,
+        });
+    }
+    current.text.clear();
+    current.heading = false;
+}
+
+fn join_paragraphs(paragraphs: &[Paragraph]) -> String {
+    paragraphs
+        .iter()
+        .map(|p| p.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn split_at_headings(paragraphs: Vec<Paragraph>) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![];
+    for paragraph in paragraphs {
+        match sections.last_mut() {
+            Some(section) if !paragraph.heading => {
+                section.text.push('\n');
+                section.text.push_str(&paragraph.text);
+            }
+            _ => sections.push(Section {
+                title: paragraph.heading.then(|| paragraph.text.clone()),
+                text: paragraph.text,
+            }),
+        }
+    }
+    sections
+}
@@ -0,0 +1,115 @@
+use mail_parser::mailbox::mbox::MessageIterator;
+use mail_parser::MessageParser;
+
+/// The detectable body of a single RFC 822 message.
+#[derive(Debug, Clone)]
+pub(crate) struct EmailBody {
+    pub(crate) message_id: Option<String>,
+    pub(crate) text: String,
+}
+
+/// Parses a single RFC 822 message, decoding MIME parts and transfer encodings.
+///
+/// `text/plain` parts are preferred; `text/html` parts are only used (converted to plain text)
+/// if a message doesn't contain any plain text body. Quoted replies and signatures are stripped.
+pub(crate) fn parse_message(raw: &[u8]) -> Option<EmailBody> {
+    let message = MessageParser::new().parse(raw)?;
+    let parts: Vec<String> = (0..message.text_body_count())
+        .filter_map(|i| message.body_text(i))
+        .map(|part| part.into_owned())
+        .collect();
+
+    Some(EmailBody {
+        message_id: message.message_id().map(|id| id.to_string()),
+        text: strip_quotes_and_signature(&parts.join("\n")),
+    })
+}
+
+/// Splits an mbox file into its messages and parses each of them.
+///
+/// Messages that can't be parsed are returned as `Err` with their position in the mailbox, so the
+/// caller can decide whether to skip them.
+pub(crate) fn parse_mbox(raw: &[u8]) -> Vec<Result<EmailBody, String>> {
+    MessageIterator::new(raw)
+        .enumerate()
+        .map(|(i, message)| {
+            let message = message.map_err(|_| format!("Invalid mbox entry at message {}", i))?;
+            parse_message(message.contents())
+                .ok_or_else(|| format!("Failed to parse message {} of mbox", i))
+        })
+        .collect()
+}
+
+/// Separators that Outlook puts in front of the original message of a reply or forward.
+const ORIGINAL_MESSAGE_SEPARATORS: &[&str] = &[
+    "-----Original Message-----",
+    "-----Ursprüngliche Nachricht-----",
+    "-----Message d'origine-----",
+    "-----Mensaje original-----",
+];
+
+/// Removes quoted replies (`> ...` lines and the attribution line ending in `:` that introduces
+/// them), everything after a signature delimiter (`-- `) and everything after the original
+/// message of an Outlook reply (`-----Original Message-----` or a `From:`/`Sent:` header block).
+fn strip_quotes_and_signature(text: &str) -> String {
+    let all: Vec<&str> = text.lines().map(|l| l.trim_end_matches('\r')).collect();
+    let mut lines: Vec<&str> = vec![];
+    for (i, &line) in all.iter().enumerate() {
+        if line == "-- " || line == "--" || is_original_message(line, all.get(i + 1).copied()) {
+            break;
+        }
+        if line.trim_start().starts_with('>') {
+            // drop the attribution line that introduces the quote, e.g. "On Mon, X wrote:" or
+            // "Am Montag schrieb X:"
+            while lines.last().is_some_and(|last| last.trim().is_empty()) {
+                lines.pop();
+            }
+            if lines
+                .last()
+                .is_some_and(|last| last.trim_end().ends_with(':'))
+            {
+                lines.pop();
+            }
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn is_original_message(line: &str, next: Option<&str>) -> bool {
+    let line = line.trim();
+    if ORIGINAL_MESSAGE_SEPARATORS
+        .iter()
+        .any(|separator| line.eq_ignore_ascii_case(separator))
+    {
+        return true;
+    }
+    let header =
+        |l: &str, names: &[&str]| names.iter().any(|name| l.trim_start().starts_with(name));
+    header(line, &["From:", "Von:", "De :", "De:"])
+        && next.is_some_and(|next| header(next, &["Sent:", "Date:", "Gesendet:", "Envoyé :"]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_quotes_and_signature;
+
+    #[test]
+    fn strips_localized_attribution_and_quote() {
+        let text = "Danke!\n\nAm Montag, 1. Juli 2024 schrieb Bob:\n> Hello\n";
+        assert_eq!(strip_quotes_and_signature(text), "Danke!");
+        let text = "Merci !\r\n\r\nLe lundi 1 juillet 2024, Bob a écrit :\r\n> Hello\r\n";
+        assert_eq!(strip_quotes_and_signature(text), "Merci !");
+    }
+
+    #[test]
+    fn strips_outlook_original_message() {
+        let text = "Danke!\n\n-----Original Message-----\nFrom: Bob\nSent: Monday\n\nHello";
+        assert_eq!(strip_quotes_and_signature(text), "Danke!");
+        let text = "Danke!\n\nFrom: Bob <bob@example.com>\nSent: Monday\nTo: Alice\n\nHello";
+        assert_eq!(strip_quotes_and_signature(text), "Danke!");
+        let text = "From: the start, it worked.\nThanks";
+        assert_eq!(strip_quotes_and_signature(text), text);
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{build_backend, DEFAULT_BACKEND};
+use crate::{DetectorConfig, LangInfo, LanguageDetector, WhatLangError};
+
+/// A member of an ensemble: the name of a backend, e.g. `whatlang-trigram`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct Voter {
+    pub(crate) name: String,
+    pub(crate) weight: f64,
+}
+
+/// The vote of a single member of an ensemble. `language` and `confidence` are `None` if the
+/// member couldn't detect the language.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Vote {
+    voter: String,
+    weight: f64,
+    language: Option<String>,
+    confidence: Option<f64>,
+}
+
+impl Vote {
+    /// Returns the name of the backend that voted.
+    pub fn voter(&self) -> &str {
+        &self.voter
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn confidence(&self) -> Option<f64> {
+        self.confidence
+    }
+}
+
+/// A backend that detects with every voter and returns the language with the largest sum of
+/// weights. Ties go to the language with the larger weighted confidence.
+#[derive(Debug)]
+pub(crate) struct Ensemble {
+    voters: Vec<(Voter, Arc<dyn LanguageDetector>)>,
+}
+
+impl Ensemble {
+    pub(crate) fn new(config: &DetectorConfig) -> Result<Ensemble, WhatLangError> {
+        if config.backend != DEFAULT_BACKEND {
+            return Err(WhatLangError::InvalidConfig {
+                message: format!(
+                    "An ensemble can't be combined with the backend '{}', add it to the voters instead",
+                    config.backend
+                ),
+            });
+        }
+        let voters = config
+            .ensemble
+            .iter()
+            .map(|voter| {
+                if !(voter.weight.is_finite() && voter.weight > 0.0) {
+                    return Err(WhatLangError::InvalidConfig {
+                        message: format!("The weight of '{}' must be positive", voter.name),
+                    });
+                }
+                Ok((voter.clone(), build_backend(&voter.name, config)?))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Ensemble { voters })
+    }
+}
+
+impl LanguageDetector for Ensemble {
+    fn detect(&self, text: &str) -> Option<LangInfo> {
+        let results: Vec<(&Voter, Option<LangInfo>)> = self
+            .voters
+            .iter()
+            .map(|(voter, detector)| (voter, detector.detect(text)))
+            .collect();
+        let total: f64 = self.voters.iter().map(|(voter, _)| voter.weight).sum();
+
+        // the winner's weight, its weighted confidence and whether any of its votes is reliable
+        let mut winner: Option<(&LangInfo, f64, f64, bool)> = None;
+        for (_, info) in &results {
+            let Some(info) = info else { continue };
+            let votes = results.iter().filter_map(|(voter, other)| match other {
+                Some(other) if other.language == info.language => Some((voter, other)),
+                _ => None,
+            });
+            let (mut weight, mut confidence, mut reliable) = (0.0, 0.0, false);
+            for (voter, other) in votes {
+                weight += voter.weight;
+                confidence += voter.weight * other.confidence;
+                reliable |= other.is_reliable;
+            }
+            if winner.is_none_or(|(_, w, c, _)| (weight, confidence) > (w, c)) {
+                winner = Some((info, weight, confidence, reliable));
+            }
+        }
+
+        let (info, weight, confidence, reliable) = winner?;
+        let agreement = weight / total;
+        Some(LangInfo {
+            language: info.language.clone(),
+            script: info.script.clone(),
+            confidence: confidence / total,
+            is_reliable: reliable && agreement > 0.5,
+            agreement: Some(agreement),
+            code: info.code.clone(),
+            composition: None,
+            normalized_characters: None,
+            votes: Some(
+                results
+                    .iter()
+                    .map(|(voter, info)| Vote {
+                        voter: voter.name.clone(),
+                        weight: voter.weight,
+                        language: info.as_ref().map(|info| info.language.clone()),
+                        confidence: info.as_ref().map(|info| info.confidence),
+                    })
+                    .collect(),
+            ),
+        })
+    }
+}
@@ -0,0 +1,296 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ScriptComposition;
+
+/// The errors of the detection functions of this crate.
+#[derive(Debug)]
+pub enum WhatLangError {
+    /// Reading a file or stdin failed
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    /// The input isn't valid UTF-8. `offset` is the byte offset of the first invalid byte
+    InvalidUtf8 {
+        path: Option<PathBuf>,
+        offset: usize,
+    },
+    /// The input isn't a JSON array of strings
+    InvalidJson {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The labeled examples aren't a CSV file with the text and label columns
+    InvalidCsv {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// The input isn't an RFC 822 message
+    InvalidEmail { path: Option<PathBuf> },
+    /// The text of a DOCX, ODT or EPUB file couldn't be extracted
+    InvalidDocument { path: PathBuf, message: String },
+    /// None of the given files could be processed
+    NoFileProcessed { files: usize },
+    /// The corpus of a benchmark contains no texts
+    NoTexts,
+    /// Writing a file or stdout failed
+    Write {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    /// The server or daemon couldn't listen on its address or socket
+    Listen { address: String, source: io::Error },
+    /// A detection run handed to a daemon failed. `code` is the code of the error the daemon
+    /// reported, or `daemon` if it couldn't be reached or didn't answer properly
+    Daemon { code: String, message: String },
+    /// whatlang couldn't detect the language of a text
+    DetectionFailed,
+    /// The detected language doesn't meet the confidence or reliability threshold
+    BelowThreshold {
+        language: String,
+        confidence: f64,
+        is_reliable: bool,
+    },
+    /// The settings of a detector are invalid, e.g. an unknown language code
+    InvalidConfig { message: String },
+}
+
+impl WhatLangError {
+    /// Returns a stable, machine-readable code of the error kind, e.g. `invalid_json`.
+    pub fn code(&self) -> &str {
+        match self {
+            WhatLangError::Io { .. } => "io",
+            WhatLangError::InvalidUtf8 { .. } => "invalid_utf8",
+            WhatLangError::InvalidJson { .. } => "invalid_json",
+            WhatLangError::InvalidCsv { .. } => "invalid_csv",
+            WhatLangError::InvalidEmail { .. } => "invalid_email",
+            WhatLangError::InvalidDocument { .. } => "invalid_document",
+            WhatLangError::NoFileProcessed { .. } => "no_file_processed",
+            WhatLangError::NoTexts => "no_texts",
+            WhatLangError::Write { .. } => "write",
+            WhatLangError::Listen { .. } => "listen",
+            WhatLangError::Daemon { code, .. } => code,
+            WhatLangError::DetectionFailed => "detection_failed",
+            WhatLangError::BelowThreshold { .. } => "below_threshold",
+            WhatLangError::InvalidConfig { .. } => "invalid_config",
+        }
+    }
+
+    /// Adds the path of the file the error occurred in, if the error concerns a single input.
+    pub(crate) fn in_file(mut self, file: &Path) -> WhatLangError {
+        match &mut self {
+            WhatLangError::Io { path, .. }
+            | WhatLangError::InvalidUtf8 { path, .. }
+            | WhatLangError::InvalidJson { path, .. }
+            | WhatLangError::InvalidCsv { path, .. }
+            | WhatLangError::InvalidEmail { path } => *path = Some(file.to_path_buf()),
+            _ => {}
+        }
+        self
+    }
+
+    /// Returns a function that wraps a failed write to `path`, or to stdout if `path` is `None`.
+    pub(crate) fn write_failed(path: Option<&Path>) -> impl Fn(io::Error) -> WhatLangError {
+        let path = path.map(Path::to_path_buf);
+        move |source| WhatLangError::Write {
+            path: path.clone(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for WhatLangError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let input = |path: &Option<PathBuf>| match path {
+            Some(path) => format!("file {:?}", path),
+            None => "input".to_string(),
+        };
+        match self {
+            WhatLangError::Io { path, source } => {
+                write!(f, "Failed to read {}: {}", input(path), source)
+            }
+            WhatLangError::InvalidUtf8 { path, offset } => write!(
+                f,
+                "Invalid UTF-8 in {} at byte offset {}",
+                input(path),
+                offset
+            ),
+            WhatLangError::InvalidJson {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid JSON in {} at line {} column {}: {}",
+                input(path),
+                line,
+                column,
+                message
+            ),
+            WhatLangError::InvalidCsv { path, message } => {
+                write!(f, "Invalid CSV in {}: {}", input(path), message)
+            }
+            WhatLangError::InvalidEmail { path } => {
+                write!(f, "Failed to parse email message in {}", input(path))
+            }
+            WhatLangError::InvalidDocument { path, message } => {
+                write!(f, "Invalid document {:?}: {}", path, message)
+            }
+            WhatLangError::NoFileProcessed { files } => {
+                write!(f, "Didn't process any of {} files due to errors", files)
+            }
+            WhatLangError::NoTexts => write!(f, "The corpus contains no texts"),
+            WhatLangError::Write { path, source } => match path {
+                Some(path) => write!(f, "Failed to write file {:?}: {}", path, source),
+                None => write!(f, "Failed to write output: {}", source),
+            },
+            WhatLangError::Listen { address, source } => {
+                write!(f, "Failed to listen on {}: {}", address, source)
+            }
+            WhatLangError::Daemon { message, .. } => write!(f, "The daemon failed: {}", message),
+            WhatLangError::DetectionFailed => write!(f, "Failed to detect language"),
+            WhatLangError::BelowThreshold {
+                language,
+                confidence,
+                is_reliable,
+            } => write!(
+                f,
+                "Detected {} with confidence {}{}, which is below the threshold",
+                language,
+                confidence,
+                if *is_reliable { "" } else { " (unreliable)" }
+            ),
+            WhatLangError::InvalidConfig { message } => {
+                write!(f, "Invalid detector config: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for WhatLangError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WhatLangError::Io { source, .. }
+            | WhatLangError::Write { source, .. }
+            | WhatLangError::Listen { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WhatLangError {
+    fn from(source: io::Error) -> Self {
+        WhatLangError::Io { path: None, source }
+    }
+}
+
+impl From<std::str::Utf8Error> for WhatLangError {
+    fn from(e: std::str::Utf8Error) -> Self {
+        WhatLangError::InvalidUtf8 {
+            path: None,
+            offset: e.valid_up_to(),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for WhatLangError {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        e.utf8_error().into()
+    }
+}
+
+impl From<csv::Error> for WhatLangError {
+    fn from(e: csv::Error) -> Self {
+        WhatLangError::InvalidCsv {
+            path: None,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for WhatLangError {
+    fn from(e: serde_json::Error) -> Self {
+        // the message of serde_json ends with the position, which is reported separately
+        let message = e.to_string();
+        let position = format!(" at line {} column {}", e.line(), e.column());
+        WhatLangError::InvalidJson {
+            path: None,
+            line: e.line(),
+            column: e.column(),
+            message: message
+                .strip_suffix(&position)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
+/// An error as it appears in the JSON output, e.g.
+/// `{"code": "detection_failed", "message": "Failed to detect language"}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ErrorInfo {
+    code: String,
+    message: String,
+    /// The characters of the text by script and kind, only set if the detector reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    composition: Option<ScriptComposition>,
+    /// The number of characters that remained after normalization, only set if the detector
+    /// normalizes texts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    normalized_characters: Option<usize>,
+}
+
+impl ErrorInfo {
+    /// Returns the code of the error kind, see [`WhatLangError::code`].
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the characters of the text that couldn't be detected, see
+    /// [`LangInfo::composition`](crate::LangInfo::composition).
+    pub fn composition(&self) -> Option<&ScriptComposition> {
+        self.composition.as_ref()
+    }
+
+    /// Returns the number of characters that remained of the text after normalization, see
+    /// [`LangInfo::normalized_characters`](crate::LangInfo::normalized_characters).
+    pub fn normalized_characters(&self) -> Option<usize> {
+        self.normalized_characters
+    }
+
+    /// Adds what the detector reports about the text besides its result.
+    pub(crate) fn with_input(
+        self,
+        composition: Option<ScriptComposition>,
+        normalized_characters: Option<usize>,
+    ) -> ErrorInfo {
+        ErrorInfo {
+            composition,
+            normalized_characters,
+            ..self
+        }
+    }
+}
+
+impl From<&WhatLangError> for ErrorInfo {
+    fn from(e: &WhatLangError) -> Self {
+        ErrorInfo {
+            code: e.code().to_string(),
+            message: e.to_string(),
+            composition: None,
+            normalized_characters: None,
+        }
+    }
+}
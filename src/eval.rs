@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::items::ItemOptions;
+use crate::split::UNDETERMINED;
+use crate::stream::Lines;
+use crate::summary::ratio;
+use crate::WhatLangError;
+
+/// How the labeled examples of the `eval` subcommand are read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LabeledFormat {
+    /// Every line is a JSON object with the text and the expected language in fields
+    #[default]
+    Jsonl,
+    /// A CSV file with a header row naming the text and expected language columns
+    Csv,
+}
+
+/// How well the detected languages of labeled examples match their expected languages.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct WhatLangEvaluation {
+    examples: usize,
+    correct: usize,
+    accuracy: f64,
+    /// The mean F1 score over all languages
+    macro_f1: f64,
+    /// The number of examples that were skipped due to errors
+    skipped: usize,
+    languages: Vec<LanguageScore>,
+    /// The number of examples per expected and detected language
+    confusion: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LanguageScore {
+    language: String,
+    /// The number of examples labeled with the language
+    support: usize,
+    /// The number of examples detected as the language
+    predicted: usize,
+    precision: f64,
+    recall: f64,
+    f1: f64,
+}
+
+/// Detects the language of every labeled example and compares it with the expected language.
+///
+/// The text is read from the `field` of `options` and the expected language from `label_field`.
+/// Expected languages may be ISO 639-3 codes or English names; both are compared by code. Results
+/// that the detector of `options` rejects count as `und`. Without inputs, the examples are read
+/// from stdin.
+pub fn evaluate(
+    inputs: Vec<PathBuf>,
+    format: LabeledFormat,
+    label_field: &str,
+    options: &ItemOptions,
+) -> Result<WhatLangEvaluation, WhatLangError> {
+    let mut confusion: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+    let mut skipped = 0;
+
+    let mut add = |example: Result<(String, String), WhatLangError>| match example {
+        Ok((text, label)) => {
+            let predicted = options
+                .detector
+                .detect(&text)
+                .info()
+                .and_then(|info| info.code())
+                .unwrap_or(UNDETERMINED)
+                .to_string();
+            *confusion
+                .entry(normalize_label(&label))
+                .or_default()
+                .entry(predicted)
+                .or_default() += 1;
+        }
+        Err(e) => {
+            error!("Invalid example: {}. Skipping example", e);
+            skipped += 1;
+        }
+    };
+
+    read_inputs(&inputs, format, label_field, &options.field, &mut add)?;
+
+    Ok(WhatLangEvaluation::from_confusion(confusion, skipped))
+}
+
+/// Passes every labeled example of the inputs, or of stdin without inputs, to `add` as a pair of
+/// its text and its label.
+pub(crate) fn read_inputs(
+    inputs: &[PathBuf],
+    format: LabeledFormat,
+    label_field: &str,
+    text_field: &str,
+    add: &mut impl FnMut(Result<(String, String), WhatLangError>),
+) -> Result<(), WhatLangError> {
+    if inputs.is_empty() {
+        read_examples(
+            std::io::stdin().lock(),
+            format,
+            label_field,
+            text_field,
+            add,
+        )?;
+    }
+    for input in inputs {
+        let file = File::open(input).map_err(|e| WhatLangError::from(e).in_file(input))?;
+        read_examples(BufReader::new(file), format, label_field, text_field, add)
+            .map_err(|e| e.in_file(input))?;
+    }
+    Ok(())
+}
+
+fn read_examples(
+    reader: impl BufRead,
+    format: LabeledFormat,
+    label_field: &str,
+    text_field: &str,
+    add: &mut impl FnMut(Result<(String, String), WhatLangError>),
+) -> Result<(), WhatLangError> {
+    match format {
+        LabeledFormat::Jsonl => {
+            for line in Lines::new(reader) {
+                match line {
+                    Ok((_, line)) if line.trim().is_empty() => {}
+                    Ok((_, line)) => add(jsonl_example(&line, text_field, label_field)),
+                    Err(e @ WhatLangError::InvalidUtf8 { .. }) => add(Err(e)),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        LabeledFormat::Csv => read_csv(reader, text_field, label_field, add)?,
+    }
+    Ok(())
+}
+
+fn jsonl_example(
+    line: &str,
+    text_field: &str,
+    label_field: &str,
+) -> Result<(String, String), WhatLangError> {
+    let invalid = |message: String| WhatLangError::InvalidJson {
+        path: None,
+        line: 1,
+        column: 1,
+        message,
+    };
+    let mut object = match serde_json::from_str(line)? {
+        Value::Object(object) => object,
+        _ => return Err(invalid("Expected a JSON object".to_string())),
+    };
+    let mut field = |name: &str| match object.remove(name) {
+        Some(Value::String(value)) => Ok(value),
+        _ => Err(invalid(format!("Missing string field '{}'", name))),
+    };
+    Ok((field(text_field)?, field(label_field)?))
+}
+
+fn read_csv(
+    reader: impl Read,
+    text_field: &str,
+    label_field: &str,
+    add: &mut impl FnMut(Result<(String, String), WhatLangError>),
+) -> Result<(), WhatLangError> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| WhatLangError::InvalidCsv {
+                path: None,
+                message: format!("Missing column '{}'", name),
+            })
+    };
+    let (text_column, label_column) = (column(text_field)?, column(label_field)?);
+
+    for record in reader.records() {
+        add(record.map_err(Into::into).and_then(|record| {
+            match (record.get(text_column), record.get(label_column)) {
+                (Some(text), Some(label)) => Ok((text.to_string(), label.to_string())),
+                _ => Err(WhatLangError::InvalidCsv {
+                    path: None,
+                    message: "Missing column".to_string(),
+                }),
+            }
+        }));
+    }
+    Ok(())
+}
+
+/// Maps an ISO 639-3 code or an English language name to its code. Other labels are kept as they
+/// are, so they never match a detected language.
+pub(crate) fn normalize_label(label: &str) -> String {
+    let label = label.trim();
+    whatlang::Lang::all()
+        .iter()
+        .find(|lang| {
+            lang.code().eq_ignore_ascii_case(label) || lang.eng_name().eq_ignore_ascii_case(label)
+        })
+        .map(|lang| lang.code().to_string())
+        .unwrap_or_else(|| label.to_string())
+}
+
+impl WhatLangEvaluation {
+    fn from_confusion(
+        confusion: BTreeMap<String, BTreeMap<String, usize>>,
+        skipped: usize,
+    ) -> WhatLangEvaluation {
+        let languages: BTreeSet<&String> = confusion
+            .iter()
+            .flat_map(|(expected, row)| std::iter::once(expected).chain(row.keys()))
+            .collect();
+
+        let languages: Vec<LanguageScore> = languages
+            .into_iter()
+            .map(|language| {
+                let correct = confusion
+                    .get(language)
+                    .and_then(|row| row.get(language))
+                    .copied()
+                    .unwrap_or(0);
+                let support = confusion.get(language).map_or(0, |row| row.values().sum());
+                let predicted = confusion.values().filter_map(|row| row.get(language)).sum();
+                let precision = ratio(correct, predicted);
+                let recall = ratio(correct, support);
+                let f1 = if precision + recall == 0.0 {
+                    0.0
+                } else {
+                    2.0 * precision * recall / (precision + recall)
+                };
+                LanguageScore {
+                    language: language.clone(),
+                    support,
+                    predicted,
+                    precision,
+                    recall,
+                    f1,
+                }
+            })
+            .collect();
+
+        let examples = confusion.values().flat_map(|row| row.values()).sum();
+        let correct = confusion
+            .iter()
+            .filter_map(|(expected, row)| row.get(expected))
+            .sum();
+        let macro_f1 = if languages.is_empty() {
+            0.0
+        } else {
+            languages.iter().map(|l| l.f1).sum::<f64>() / languages.len() as f64
+        };
+
+        WhatLangEvaluation {
+            examples,
+            correct,
+            accuracy: ratio(correct, examples),
+            macro_f1,
+            skipped,
+            languages,
+            confusion,
+        }
+    }
+}
+
+/// Formats the evaluation as a plain text report with a table of per-language scores and the
+/// confusion matrix, with expected languages as rows and detected languages as columns.
+impl fmt::Display for WhatLangEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "examples: {}, correct: {}, accuracy: {:.4}, macro F1: {:.4}, skipped: {}",
+            self.examples, self.correct, self.accuracy, self.macro_f1, self.skipped
+        )?;
+        writeln!(f)?;
+
+        let width = self
+            .languages
+            .iter()
+            .map(|l| l.language.chars().count())
+            .chain(std::iter::once("language".len()))
+            .max()
+            .unwrap_or_default();
+        writeln!(
+            f,
+            "{:<width$}  {:>7}  {:>9}  {:>9}  {:>6}  {:>6}",
+            "language", "support", "predicted", "precision", "recall", "f1"
+        )?;
+        for l in &self.languages {
+            writeln!(
+                f,
+                "{:<width$}  {:>7}  {:>9}  {:>9.4}  {:>6.4}  {:>6.4}",
+                l.language, l.support, l.predicted, l.precision, l.recall, l.f1
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "confusion matrix (rows: expected, columns: detected)")?;
+        let columns: Vec<&String> = self.languages.iter().map(|l| &l.language).collect();
+        let cell = columns
+            .iter()
+            .map(|c| c.chars().count())
+            .chain(
+                self.confusion
+                    .values()
+                    .flat_map(|row| row.values())
+                    .map(|n| n.to_string().len()),
+            )
+            .max()
+            .unwrap_or_default();
+        write!(f, "{:<width$}", "")?;
+        for column in &columns {
+            write!(f, "  {:>cell$}", column)?;
+        }
+        writeln!(f)?;
+        for (expected, row) in &self.confusion {
+            write!(f, "{:<width$}", expected)?;
+            for column in &columns {
+                write!(f, "  {:>cell$}", row.get(*column).copied().unwrap_or(0))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
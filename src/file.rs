@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::WhatLangError;
+
+/// The bytes of a file. Regular files are mapped into memory, so that their text can be validated
+/// and detected in place instead of being copied into a buffer first.
+pub(crate) enum FileBytes {
+    Mapped(Mmap),
+    /// Pipes and other special files, empty files and files that couldn't be mapped
+    Read(Vec<u8>),
+}
+
+impl FileBytes {
+    pub(crate) fn read(path: &Path) -> Result<FileBytes, WhatLangError> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        // empty files can't be mapped on every platform
+        if metadata.is_file() && metadata.len() > 0 {
+            // SAFETY: the mapping is only read. Like every tool that maps its inputs, this relies
+            // on the file not being modified or truncated while it is detected.
+            if let Ok(mmap) = unsafe { Mmap::map(&file) } {
+                return Ok(FileBytes::Mapped(mmap));
+            }
+        }
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        Ok(FileBytes::Read(buffer))
+    }
+}
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Mapped(mmap) => mmap,
+            FileBytes::Read(buffer) => buffer,
+        }
+    }
+}
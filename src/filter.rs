@@ -0,0 +1,57 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use log::error;
+
+use crate::items::{for_each_item, ItemOptions};
+use crate::split::UNDETERMINED;
+use crate::WhatLangError;
+
+/// Writes every item whose language is one of `languages` (ISO 639-3 codes, in any case) unchanged
+/// into `out`, one item per line. `und` matches items whose language couldn't be detected or that
+/// don't meet the requirements of `options`. Without languages, every detected item matches. With
+/// `invert`, the items that don't match are written instead.
+///
+/// Returns the number of written items.
+pub fn filter_items(
+    inputs: Vec<PathBuf>,
+    options: &ItemOptions,
+    languages: &[String],
+    invert: bool,
+    out: &mut impl Write,
+) -> Result<usize, WhatLangError> {
+    let mut written = 0;
+    for_each_item(&inputs, options.format, |item| {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                error!("{}. Skipping item", e);
+                return Ok(());
+            }
+        };
+        let code = match options.detect(item) {
+            Ok(info) => info.and_then(|info| info.code().map(str::to_string)),
+            Err(e) => {
+                error!("Invalid item '{}': {}. Skipping item", item, e);
+                return Ok(());
+            }
+        };
+        let matches = match code {
+            Some(code) => {
+                languages.is_empty() || languages.iter().any(|l| l.eq_ignore_ascii_case(&code))
+            }
+            None => languages
+                .iter()
+                .any(|l| l.eq_ignore_ascii_case(UNDETERMINED)),
+        };
+        if matches != invert {
+            out.write_all(item.as_bytes())
+                .and_then(|_| out.write_all(b"\n"))
+                .map_err(WhatLangError::write_failed(None))?;
+            written += 1;
+        }
+        Ok(())
+    })?;
+    out.flush().map_err(WhatLangError::write_failed(None))?;
+    Ok(written)
+}
@@ -0,0 +1,108 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::stream::{jsonl_text, trim_newline, Lines};
+use crate::{LangDetector, LangInfo, WhatLangError, WhatLangResult};
+
+/// How the items of the `split` and `filter` subcommands are read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ItemFormat {
+    /// Every line is a plain text
+    #[default]
+    Lines,
+    /// Every line is a JSON string or a JSON object with the text in a field
+    Jsonl,
+    /// Every item is the path of a file
+    Files,
+}
+
+/// Options shared by the subcommands that process items one by one.
+#[derive(Debug, Clone)]
+pub struct ItemOptions {
+    pub format: ItemFormat,
+    /// The field of a JSONL object that contains the text
+    pub field: String,
+    /// Detects the items. Results it rejects are treated as undetected
+    pub detector: LangDetector,
+}
+
+impl Default for ItemOptions {
+    fn default() -> Self {
+        ItemOptions {
+            format: ItemFormat::default(),
+            field: "text".to_string(),
+            detector: LangDetector::default(),
+        }
+    }
+}
+
+impl ItemOptions {
+    /// Detects the language of an item, returning `None` if it couldn't be detected or the
+    /// detector rejected the result.
+    pub(crate) fn detect(&self, item: &str) -> Result<Option<LangInfo>, WhatLangError> {
+        Ok(match self.detector.detect(&self.text(item)?) {
+            WhatLangResult::Ok(info) => Some(info),
+            WhatLangResult::Error(_) | WhatLangResult::TooShort(_) => None,
+        })
+    }
+
+    /// Returns the text of an item.
+    pub(crate) fn text(&self, item: &str) -> Result<String, WhatLangError> {
+        Ok(match self.format {
+            ItemFormat::Lines => item.to_string(),
+            ItemFormat::Jsonl => jsonl_text(item, &self.field)?,
+            ItemFormat::Files => self.detector.read_text_file(Path::new(item))?,
+        })
+    }
+}
+
+/// Calls `f` for every item of the inputs. Items of the `Lines` and `Jsonl` formats are the lines
+/// of the input files, items of the `Files` format are the input files themselves. Without
+/// inputs, the items are read line by line from stdin.
+///
+/// A line that isn't valid UTF-8 is passed to `f` as an `InvalidUtf8` error, and reading continues
+/// with the next line.
+pub(crate) fn for_each_item(
+    inputs: &[PathBuf],
+    format: ItemFormat,
+    mut f: impl FnMut(Result<&str, WhatLangError>) -> Result<(), WhatLangError>,
+) -> Result<(), WhatLangError> {
+    if inputs.is_empty() {
+        return for_each_line(std::io::stdin().lock(), None, &mut f);
+    }
+
+    for input in inputs {
+        if format == ItemFormat::Files {
+            f(Ok(&input.to_string_lossy()))?;
+            continue;
+        }
+        let file = File::open(input).map_err(|e| WhatLangError::from(e).in_file(input))?;
+        let reader = BufReader::new(file);
+        for_each_line(reader, Some(input), &mut f)?;
+    }
+    Ok(())
+}
+
+fn for_each_line(
+    reader: impl BufRead,
+    path: Option<&Path>,
+    f: &mut impl FnMut(Result<&str, WhatLangError>) -> Result<(), WhatLangError>,
+) -> Result<(), WhatLangError> {
+    for line in Lines::new(reader) {
+        match line {
+            Ok((_, line)) => f(Ok(trim_newline(&line)))?,
+            Err(e) => {
+                let e = match path {
+                    Some(path) => e.in_file(path),
+                    None => e,
+                };
+                match e {
+                    WhatLangError::InvalidUtf8 { .. } => f(Err(e))?,
+                    e => return Err(e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,64 @@
-use std::error::Error;
-use std::fs::File;
+use std::borrow::Cow;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use log::error;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub use crate::composition::{script_composition, ScriptComposition, ScriptShare};
+use crate::document::DocumentKind;
+use crate::ensemble::{Ensemble, Voter};
+use crate::file::FileBytes;
+use crate::profile::{Profiles, PROFILES_BACKEND};
+pub use crate::segment::{
+    detect_sentences, merge_distributions, segment, LanguageShare, SegmentMode,
+    WhatLangSegmentation, WhatLangSpan,
+};
+use crate::summary::Tally;
+pub use crate::summary::{summarize, LanguageSummary, ScriptSummary, WhatLangSummary};
+
+pub use crate::backend::{backends, register_backend, LanguageDetector, DEFAULT_BACKEND};
+pub use crate::bench::{benchmark, BenchOptions, WhatLangBenchmark, WhatLangBenchmarkRun};
+#[cfg(unix)]
+pub use crate::daemon::{default_socket, request_daemon, run_daemon};
+pub use crate::ensemble::Vote;
+pub use crate::error::{ErrorInfo, WhatLangError};
+pub use crate::eval::{evaluate, LabeledFormat, LanguageScore, WhatLangEvaluation};
+pub use crate::filter::filter_items;
+pub use crate::items::{ItemFormat, ItemOptions};
+pub use crate::normalize::NormalizationForm;
+pub use crate::profile::{
+    train_profiles, LanguageProfile, LanguageProfiles, TrainOptions, DEFAULT_PROFILE_SIZE,
+};
+pub use crate::sample::{FileSample, SampleStrategy, Sampling, DEFAULT_CHUNKS};
+pub use crate::serve::{serve, ServeOptions};
+pub use crate::split::{split_items, WhatLangSplitReport, UNDETERMINED};
+pub use crate::worker::run_worker;
+
+mod backend;
+mod bench;
+mod composition;
+#[cfg(unix)]
+mod daemon;
+mod document;
+mod email;
+mod ensemble;
+mod error;
+mod eval;
+mod file;
+mod filter;
+mod items;
+mod normalize;
+mod profile;
+mod sample;
+mod segment;
+mod serve;
+mod split;
+mod stream;
+mod summary;
+mod worker;
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct LangInfo {
@@ -13,115 +66,1223 @@ pub struct LangInfo {
     script: String,
     confidence: f64,
     is_reliable: bool,
+    /// The share of the ensemble's weight that voted for the language, only set for ensembles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    agreement: Option<f64>,
+    /// The votes of every member of the ensemble, only set for ensembles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    votes: Option<Vec<Vote>>,
+    /// The code of a language of a trained profile, only set for these languages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    /// The characters of the text by script and kind, only set if the detector reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    composition: Option<ScriptComposition>,
+    /// The number of characters that remained after normalization, only set if the detector
+    /// normalizes texts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    normalized_characters: Option<usize>,
 }
 
 impl LangInfo {
+    /// Returns the result of a backend other than whatlang. `language` is the English name of the
+    /// language, e.g. `German`.
+    pub fn new(
+        language: impl Into<String>,
+        script: impl Into<String>,
+        confidence: f64,
+        is_reliable: bool,
+    ) -> LangInfo {
+        LangInfo {
+            language: language.into(),
+            script: script.into(),
+            confidence,
+            is_reliable,
+            agreement: None,
+            votes: None,
+            code: None,
+            composition: None,
+            normalized_characters: None,
+        }
+    }
+
     pub fn from_info(info: whatlang::Info) -> LangInfo {
         LangInfo {
             language: info.lang().eng_name().to_string(),
             script: info.script().to_string(),
             confidence: info.confidence(),
             is_reliable: info.is_reliable(),
+            agreement: None,
+            votes: None,
+            code: None,
+            composition: None,
+            normalized_characters: None,
         }
     }
+
+    /// Returns the English name of the language, e.g. `German`.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the name of the script, e.g. `Latin`.
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    pub fn is_reliable(&self) -> bool {
+        self.is_reliable
+    }
+
+    /// Returns the share of the ensemble's weight that voted for the language, or `None` if the
+    /// language wasn't detected by an ensemble.
+    pub fn agreement(&self) -> Option<f64> {
+        self.agreement
+    }
+
+    /// Returns the votes of every member of the ensemble, or `None` if the language wasn't
+    /// detected by an ensemble.
+    pub fn votes(&self) -> Option<&[Vote]> {
+        self.votes.as_deref()
+    }
+
+    /// Returns the characters of the text by script and kind, or `None` if the detector doesn't
+    /// report them, see [`DetectorConfig::composition`].
+    pub fn composition(&self) -> Option<&ScriptComposition> {
+        self.composition.as_ref()
+    }
+
+    /// Returns the number of characters that remained after normalization, or `None` if the
+    /// detector doesn't normalize texts.
+    pub fn normalized_characters(&self) -> Option<usize> {
+        self.normalized_characters
+    }
+
+    /// Returns the ISO 639-3 code of the language, e.g. `deu` for German, or the code of the
+    /// language of a trained profile, e.g. `gsw`.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref().or_else(|| {
+            whatlang::Lang::all()
+                .iter()
+                .find(|lang| lang.eng_name() == self.language)
+                .map(|lang| lang.code())
+        })
+    }
+}
+
+/// A text that is shorter than the minimum length of the detector, so that its language wasn't
+/// detected, see [`DetectorConfig::min_chars`] and [`DetectorConfig::min_words`].
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TooShortInfo {
+    /// The number of characters of the text other than whitespace, after normalization
+    characters: usize,
+    /// The number of words of the text, after normalization
+    words: usize,
+    /// The characters of the text by script and kind, only set if the detector reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    composition: Option<ScriptComposition>,
+}
+
+impl TooShortInfo {
+    pub fn characters(&self) -> usize {
+        self.characters
+    }
+
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// Returns the characters of the text by script and kind, see [`LangInfo::composition`].
+    pub fn composition(&self) -> Option<&ScriptComposition> {
+        self.composition.as_ref()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum WhatLangResult {
     Ok(LangInfo),
-    Error(String),
+    Error(ErrorInfo),
+    /// The text is too short to detect its language
+    TooShort(TooShortInfo),
+}
+
+impl WhatLangResult {
+    /// Returns the detected language, or `None` if detection failed or the text was too short.
+    pub fn info(&self) -> Option<&LangInfo> {
+        match self {
+            WhatLangResult::Ok(info) => Some(info),
+            WhatLangResult::Error(_) | WhatLangResult::TooShort(_) => None,
+        }
+    }
+
+    /// Returns the error, or `None` if detection succeeded or the text was too short.
+    pub fn error(&self) -> Option<&ErrorInfo> {
+        match self {
+            WhatLangResult::Ok(_) | WhatLangResult::TooShort(_) => None,
+            WhatLangResult::Error(message) => Some(message),
+        }
+    }
+
+    /// Returns the length of the text, or `None` if the text wasn't too short.
+    pub fn too_short(&self) -> Option<&TooShortInfo> {
+        match self {
+            WhatLangResult::TooShort(info) => Some(info),
+            WhatLangResult::Ok(_) | WhatLangResult::Error(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WhatLangFromFileResult {
     file: PathBuf,
     results: Vec<WhatLangResult>,
+    /// The Message-IDs of the messages in an email or mbox file, in the same order as `results`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message_ids: Option<Vec<Option<String>>>,
+    /// The results per chapter or section of a DOCX, ODT or EPUB file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sections: Option<Vec<WhatLangFromSectionResult>>,
+    /// The language spans of every text, in the same order as `results`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    segments: Option<Vec<WhatLangSegmentation>>,
+    /// The results per sentence of every text, in the same order as `results`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sentences: Option<Vec<Vec<WhatLangSpan>>>,
+    /// The share of every language in all texts of the file, weighted by character count
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    distribution: Option<Vec<LanguageShare>>,
+    /// The part of the file that was read, only set if the file was larger than the byte limit
+    /// of the detector's sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sample: Option<FileSample>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhatLangFromSectionResult {
+    title: Option<String>,
+    result: WhatLangResult,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhatLangFromMessageResult {
+    message_id: Option<String>,
+    result: WhatLangResult,
+}
+
+/// The result of a text of a plain text or JSON input, depending on the [`Granularity`].
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum WhatLangFromTextResult {
+    Text(WhatLangResult),
+    Sentences(Vec<WhatLangSpan>),
+    Segments(WhatLangSegmentation),
+}
+
+/// The results of a detection run, shaped like its input.
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum WhatLangResults {
+    /// The result of a plain text
+    Text(WhatLangFromTextResult),
+    /// The results of the strings of a JSON array
+    Texts(Vec<WhatLangFromTextResult>),
+    /// The result of a single email
+    Message(WhatLangFromMessageResult),
+    /// The results of the messages of an mbox file
+    Messages(Vec<WhatLangFromMessageResult>),
+    Files(Vec<WhatLangFromFileResult>),
+}
+
+/// The output of a detection run, see [`Report`]. It serializes to the JSON output of the CLI.
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum WhatLangReport {
+    Results(WhatLangResults),
+    ResultsWithSummary {
+        results: WhatLangResults,
+        summary: WhatLangSummary,
+    },
+    Summary(WhatLangSummary),
 }
 
-pub fn process_string(arg: String, json: bool) -> Result<Value, Box<dyn Error>> {
-    match json {
-        true => {
-            let texts = validate_json(&arg)?;
-            let result = detect_many(texts);
-            Ok(json!(result))
+impl WhatLangFromFileResult {
+    pub fn file(&self) -> &PathBuf {
+        &self.file
+    }
+
+    pub fn results(&self) -> &[WhatLangResult] {
+        &self.results
+    }
+
+    pub fn message_ids(&self) -> Option<&[Option<String>]> {
+        self.message_ids.as_deref()
+    }
+
+    pub fn sections(&self) -> Option<&[WhatLangFromSectionResult]> {
+        self.sections.as_deref()
+    }
+
+    pub fn segments(&self) -> Option<&[WhatLangSegmentation]> {
+        self.segments.as_deref()
+    }
+
+    pub fn sentences(&self) -> Option<&[Vec<WhatLangSpan>]> {
+        self.sentences.as_deref()
+    }
+
+    pub fn distribution(&self) -> Option<&[LanguageShare]> {
+        self.distribution.as_deref()
+    }
+
+    /// Returns which part of the file was read, or `None` if the whole file was read.
+    pub fn sample(&self) -> Option<&FileSample> {
+        self.sample.as_ref()
+    }
+}
+
+impl WhatLangFromSectionResult {
+    /// Returns the title of the chapter or the heading of the section, if any.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn result(&self) -> &WhatLangResult {
+        &self.result
+    }
+}
+
+impl WhatLangFromMessageResult {
+    pub fn message_id(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    pub fn result(&self) -> &WhatLangResult {
+        &self.result
+    }
+}
+
+/// How the input of any input mode is interpreted.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputFormat {
+    /// A single plain text
+    #[default]
+    Plain,
+    /// A JSON array of strings
+    Json,
+    /// A single RFC 822 message
+    Email,
+    /// An mbox file containing any number of RFC 822 messages
+    Mbox,
+}
+
+/// Which parts of a plain text or JSON input are detected.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    /// Every text as a whole
+    #[default]
+    Text,
+    /// Every sentence of a text on its own, see [`detect_sentences`]
+    Sentences,
+    /// Spans of the same language, see [`segment`]
+    Segments(SegmentMode),
+}
+
+/// How files are read and which results are reported for them, see [`LangDetector::detect_files`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileOptions {
+    pub format: InputFormat,
+    /// Additionally detect every chapter or section of DOCX, ODT and EPUB files
+    pub sections: bool,
+    pub granularity: Granularity,
+    /// Additionally report the share of every language in each file
+    pub distribution: bool,
+}
+
+/// Whether the results, a summary of them or both are returned.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Report {
+    /// The results only
+    #[default]
+    Results,
+    /// An object with the `results` and their `summary`
+    ResultsWithSummary,
+    /// The summary only
+    Summary,
+}
+
+/// The method whatlang uses to detect the language of a text.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// Trigrams for Latin and Cyrillic scripts, alphabets for the others
+    #[default]
+    Combined,
+    /// Trigram frequencies only
+    Trigram,
+    /// Characters of the alphabets only
+    Alphabet,
+}
+
+impl Method {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Combined => "combined",
+            Method::Trigram => "trigram",
+            Method::Alphabet => "alphabet",
         }
-        false => {
-            let result = detect(&arg);
-            Ok(json!(result))
+    }
+
+    /// Returns the method named `name`, e.g. `trigram`.
+    pub fn from_name(name: &str) -> Option<Method> {
+        [Method::Combined, Method::Trigram, Method::Alphabet]
+            .into_iter()
+            .find(|method| method.name() == name)
+    }
+
+    pub(crate) fn options(&self) -> whatlang::dev::Options {
+        whatlang::dev::Options::with_method(match self {
+            Method::Combined => whatlang::dev::Method::Combined,
+            Method::Trigram => whatlang::dev::Method::Trigram,
+            Method::Alphabet => whatlang::dev::Method::Alphabet,
+        })
+    }
+}
+
+/// The settings of a [`LangDetector`]: the backend and method, the languages that may be detected,
+/// the preprocessing of texts and the thresholds results have to meet.
+///
+/// ```
+/// use whatlang_cli::{DetectorConfig, Method};
+///
+/// let detector = DetectorConfig::new()
+///     .method(Method::Trigram)
+///     .allowlist(["deu", "eng"])
+///     .min_confidence(0.5)
+///     .build()
+///     .unwrap();
+/// let result = detector.detect("Das ist ein deutscher Satz.");
+/// assert_eq!(result.info().unwrap().language(), "German");
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DetectorConfig {
+    /// The name of a registered backend, see [`register_backend`]
+    backend: String,
+    /// The detection method of the whatlang backend
+    method: Method,
+    /// The ISO 639-3 codes of the only languages that are detected, all languages if empty
+    allowlist: Vec<String>,
+    /// The ISO 639-3 codes of languages that are never detected
+    denylist: Vec<String>,
+    /// Results below this confidence are rejected
+    min_confidence: f64,
+    /// Unreliable results are rejected
+    reliable_only: bool,
+    /// The Unicode normalization form texts are converted to before detection
+    normalize: NormalizationForm,
+    /// Texts are lowercased before detection
+    lowercase: bool,
+    /// URLs and email addresses are removed before detection
+    strip_urls: bool,
+    /// `@mentions` are removed before detection
+    strip_mentions: bool,
+    /// `#hashtags` are removed before detection
+    strip_hashtags: bool,
+    /// Emoji are removed before detection
+    strip_emoji: bool,
+    /// Numbers are removed before detection
+    strip_numbers: bool,
+    /// Runs of whitespace are replaced by a single space before detection
+    collapse_whitespace: bool,
+    /// The voters of an ensemble, which detects instead of the backend if not empty
+    ensemble: Vec<Voter>,
+    /// Files of trained language profiles, detected alongside the backend
+    profiles: Vec<PathBuf>,
+    /// The script composition of every text is reported with its result
+    composition: bool,
+    /// Texts with fewer characters other than whitespace are too short to be detected
+    min_chars: usize,
+    /// Texts with fewer words are too short to be detected
+    min_words: usize,
+    /// Only part of every plain text file larger than the byte limit is read
+    sampling: Option<Sampling>,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        DetectorConfig {
+            backend: DEFAULT_BACKEND.to_string(),
+            method: Method::default(),
+            allowlist: vec![],
+            denylist: vec![],
+            min_confidence: 0.0,
+            reliable_only: false,
+            normalize: NormalizationForm::None,
+            lowercase: false,
+            strip_urls: false,
+            strip_mentions: false,
+            strip_hashtags: false,
+            strip_emoji: false,
+            strip_numbers: false,
+            collapse_whitespace: false,
+            ensemble: vec![],
+            profiles: vec![],
+            composition: false,
+            min_chars: 0,
+            min_words: 0,
+            sampling: None,
         }
     }
 }
 
-pub fn process_stdin(json: bool) -> Result<Value, Box<dyn Error>> {
-    let text = validate_stdin_input()?;
-    process_string(text, json)
+impl DetectorConfig {
+    pub fn new() -> DetectorConfig {
+        DetectorConfig::default()
+    }
+
+    /// Detects with the backend registered under `name`, see [`register_backend`].
+    pub fn backend(mut self, name: impl Into<String>) -> DetectorConfig {
+        self.backend = name.into();
+        self
+    }
+
+    /// Detects with the given method of whatlang. Only used by the `whatlang` backend.
+    pub fn method(mut self, method: Method) -> DetectorConfig {
+        self.method = method;
+        self
+    }
+
+    /// Only detects the languages with the given ISO 639-3 codes, e.g. `deu`.
+    pub fn allowlist<S: Into<String>>(
+        mut self,
+        codes: impl IntoIterator<Item = S>,
+    ) -> DetectorConfig {
+        self.allowlist = codes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Never detects the languages with the given ISO 639-3 codes, e.g. `deu`.
+    pub fn denylist<S: Into<String>>(
+        mut self,
+        codes: impl IntoIterator<Item = S>,
+    ) -> DetectorConfig {
+        self.denylist = codes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Rejects results below this confidence with a `below_threshold` error.
+    pub fn min_confidence(mut self, min_confidence: f64) -> DetectorConfig {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Rejects unreliable results with a `below_threshold` error.
+    pub fn reliable_only(mut self, reliable_only: bool) -> DetectorConfig {
+        self.reliable_only = reliable_only;
+        self
+    }
+
+    /// Converts texts to a Unicode normalization form before detection.
+    pub fn normalize(mut self, form: NormalizationForm) -> DetectorConfig {
+        self.normalize = form;
+        self
+    }
+
+    /// Lowercases texts before detection.
+    pub fn lowercase(mut self, lowercase: bool) -> DetectorConfig {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Removes URLs and email addresses before detection.
+    pub fn strip_urls(mut self, strip_urls: bool) -> DetectorConfig {
+        self.strip_urls = strip_urls;
+        self
+    }
+
+    /// Removes `@mentions` before detection.
+    pub fn strip_mentions(mut self, strip_mentions: bool) -> DetectorConfig {
+        self.strip_mentions = strip_mentions;
+        self
+    }
+
+    /// Removes `#hashtags` before detection.
+    pub fn strip_hashtags(mut self, strip_hashtags: bool) -> DetectorConfig {
+        self.strip_hashtags = strip_hashtags;
+        self
+    }
+
+    /// Removes emoji before detection.
+    pub fn strip_emoji(mut self, strip_emoji: bool) -> DetectorConfig {
+        self.strip_emoji = strip_emoji;
+        self
+    }
+
+    /// Removes numbers, e.g. `2024`, `3.5` or `10%`, before detection.
+    pub fn strip_numbers(mut self, strip_numbers: bool) -> DetectorConfig {
+        self.strip_numbers = strip_numbers;
+        self
+    }
+
+    /// Replaces runs of whitespace by a single space before detection.
+    pub fn collapse_whitespace(mut self, collapse_whitespace: bool) -> DetectorConfig {
+        self.collapse_whitespace = collapse_whitespace;
+        self
+    }
+
+    /// Detects with every voter and combines their votes, weighted by the given weights. A voter
+    /// is the name of a registered backend, e.g. `profiles`, or `whatlang-combined`,
+    /// `whatlang-trigram` and `whatlang-alphabet` for the methods of whatlang. The result reports
+    /// the agreement of the voters and their individual votes.
+    pub fn ensemble<S: Into<String>>(
+        mut self,
+        voters: impl IntoIterator<Item = (S, f64)>,
+    ) -> DetectorConfig {
+        self.ensemble = voters
+            .into_iter()
+            .map(|(name, weight)| Voter {
+                name: name.into(),
+                weight,
+            })
+            .collect();
+        self
+    }
+
+    /// Also detects the languages of the profiles in the given files, which are written by
+    /// [`LanguageProfiles::save`]. The allowlist and the denylist may contain their codes.
+    pub fn profiles<P: Into<PathBuf>>(
+        mut self,
+        files: impl IntoIterator<Item = P>,
+    ) -> DetectorConfig {
+        self.profiles = files.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Reports the letters of every script and the digits, punctuation and emoji of every text
+    /// with its result, see [`script_composition`].
+    pub fn composition(mut self, composition: bool) -> DetectorConfig {
+        self.composition = composition;
+        self
+    }
+
+    /// Returns texts with fewer characters other than whitespace, after normalization, as
+    /// `TooShort` results instead of detecting them.
+    pub fn min_chars(mut self, min_chars: usize) -> DetectorConfig {
+        self.min_chars = min_chars;
+        self
+    }
+
+    /// Returns texts with fewer words, after normalization, as `TooShort` results instead of
+    /// detecting them.
+    pub fn min_words(mut self, min_words: usize) -> DetectorConfig {
+        self.min_words = min_words;
+        self
+    }
+
+    /// Reads only a sample of every plain text file that is larger than the byte limit of the
+    /// sampling. JSON files, emails and documents are always read whole.
+    pub fn sampling(mut self, sampling: Option<Sampling>) -> DetectorConfig {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Returns whether the allowlist and the denylist permit the language with the ISO 639-3
+    /// code `code`. Backends other than whatlang use it to honor the lists.
+    pub fn allows(&self, code: &str) -> bool {
+        let listed = |list: &[String]| list.iter().any(|c| c.eq_ignore_ascii_case(code));
+        (self.allowlist.is_empty() || listed(&self.allowlist)) && !listed(&self.denylist)
+    }
+
+    /// Returns a detector with these settings, or an error if the backend or a language code is
+    /// unknown, a profile file can't be loaded or both an allowlist and a denylist are given.
+    pub fn build(&self) -> Result<LangDetector, WhatLangError> {
+        if !self.allowlist.is_empty() && !self.denylist.is_empty() {
+            return Err(WhatLangError::InvalidConfig {
+                message: "An allowlist and a denylist can't be combined".to_string(),
+            });
+        }
+        if self.sampling.as_ref().is_some_and(|s| s.max_bytes() == 0) {
+            return Err(WhatLangError::InvalidConfig {
+                message: "The byte limit of the sampling must be positive".to_string(),
+            });
+        }
+        let build = |config: &DetectorConfig| -> Result<Arc<dyn LanguageDetector>, _> {
+            if config.ensemble.is_empty() {
+                backend::build_backend(&config.backend, config)
+            } else {
+                Ok(Arc::new(Ensemble::new(config)?))
+            }
+        };
+        // the profiles backend, also as a voter, detects with the profiles itself
+        let detects_profiles = self.backend == PROFILES_BACKEND
+            || self
+                .ensemble
+                .iter()
+                .any(|voter| voter.name == PROFILES_BACKEND);
+        let backend = if self.profiles.is_empty() || detects_profiles {
+            build(self)?
+        } else {
+            Arc::new(Profiles::new(self, build)?)
+        };
+        Ok(LangDetector {
+            backend,
+            config: self.clone(),
+        })
+    }
 }
 
-pub fn process_files(files: Vec<PathBuf>, json: bool) -> Result<Value, Box<dyn Error>> {
-    let mut result: Vec<WhatLangFromFileResult> = vec![];
-    for file in files {
-        let text: String = match validate_file_input(&file) {
-            Ok(t) => t,
-            Err(e) => {
-                error!("Invalid file {:?}: {}. Skipping file", file, e);
-                continue;
+/// Detects languages with the settings of a [`DetectorConfig`], configured once and reused for
+/// any number of texts. Texts are detected by the configured [`LanguageDetector`] backend.
+///
+/// All detection functions of this crate are methods of a detector. The free functions use
+/// [`LangDetector::default`], which detects every language with whatlang's combined method.
+#[derive(Debug, Clone)]
+pub struct LangDetector {
+    config: DetectorConfig,
+    backend: Arc<dyn LanguageDetector>,
+}
+
+impl Default for LangDetector {
+    fn default() -> Self {
+        LangDetector {
+            config: DetectorConfig::default(),
+            backend: Arc::new(backend::Whatlang::default()),
+        }
+    }
+}
+
+impl LangDetector {
+    pub fn config(&self) -> &DetectorConfig {
+        &self.config
+    }
+
+    /// Detects the language of a text. Texts below the minimum length are returned as `TooShort`
+    /// results and results that don't meet the thresholds as `below_threshold` errors.
+    pub fn detect(&self, text: &str) -> WhatLangResult {
+        let composition = self.config.composition.then(|| script_composition(text));
+        let text = self.config.normalize_text(text);
+        let normalized_characters = self.config.normalizes().then(|| text.chars().count());
+        if self.config.min_chars > 0 || self.config.min_words > 0 {
+            let characters = text.chars().filter(|c| !c.is_whitespace()).count();
+            let words = text.unicode_words().count();
+            if characters < self.config.min_chars || words < self.config.min_words {
+                return WhatLangResult::TooShort(TooShortInfo {
+                    characters,
+                    words,
+                    composition,
+                });
+            }
+        }
+        let info = match self.backend.detect(&text) {
+            Some(info) => info,
+            None => {
+                let error = ErrorInfo::from(&WhatLangError::DetectionFailed);
+                return WhatLangResult::Error(error.with_input(composition, normalized_characters));
             }
         };
+        if info.confidence < self.config.min_confidence
+            || (self.config.reliable_only && !info.is_reliable)
+        {
+            let e = WhatLangError::BelowThreshold {
+                language: info.language,
+                confidence: info.confidence,
+                is_reliable: info.is_reliable,
+            };
+            let error = ErrorInfo::from(&e);
+            return WhatLangResult::Error(error.with_input(composition, normalized_characters));
+        }
+        WhatLangResult::Ok(LangInfo {
+            composition,
+            normalized_characters,
+            ..info
+        })
+    }
 
-        match json {
-            true => {
-                let texts = match validate_json(&text) {
-                    Ok(t) => t,
-                    Err(e) => {
-                        error!("Invalid json in file {:?}: {}. Skipping file", file, e);
-                        continue;
-                    }
-                };
-                let results = detect_many(texts);
-                result.push(WhatLangFromFileResult { file, results })
+    pub fn detect_many(&self, texts: &[String]) -> Vec<WhatLangResult> {
+        texts.iter().map(|text| self.detect(text)).collect()
+    }
+
+    /// Detects the language of every string of a JSON array of strings.
+    pub fn detect_json(&self, json: &str) -> Result<Vec<WhatLangResult>, WhatLangError> {
+        Ok(self.detect_many(&validate_json(json)?))
+    }
+
+    /// Detects the language of the text read from stdin.
+    pub fn detect_stdin(&self) -> Result<WhatLangResult, WhatLangError> {
+        Ok(self.detect(&validate_utf8(read_stdin_input()?)?))
+    }
+
+    /// Detects the language of every string of a JSON array of strings read from stdin.
+    pub fn detect_stdin_json(&self) -> Result<Vec<WhatLangResult>, WhatLangError> {
+        self.detect_json(&validate_utf8(read_stdin_input()?)?)
+    }
+
+    /// Detects the languages of files as [`LangDetector::process_files`] does, but returns the
+    /// typed results. Files that can't be processed are logged and skipped.
+    pub fn detect_files(
+        &self,
+        files: Vec<PathBuf>,
+        options: &FileOptions,
+    ) -> Result<Vec<WhatLangFromFileResult>, WhatLangError> {
+        self.detect_files_tallied(files, options, &mut Tally::new(false))
+    }
+
+    pub fn process_string(
+        &self,
+        arg: String,
+        format: InputFormat,
+        granularity: Granularity,
+        report: Report,
+    ) -> Result<WhatLangReport, WhatLangError> {
+        self.process_bytes(arg.into_bytes(), format, granularity, report)
+    }
+
+    pub fn process_stdin(
+        &self,
+        format: InputFormat,
+        granularity: Granularity,
+        report: Report,
+    ) -> Result<WhatLangReport, WhatLangError> {
+        let buffer = read_stdin_input()?;
+        self.process_bytes(buffer, format, granularity, report)
+    }
+
+    pub fn process_files(
+        &self,
+        files: Vec<PathBuf>,
+        options: &FileOptions,
+        report: Report,
+    ) -> Result<WhatLangReport, WhatLangError> {
+        let mut tally = Tally::for_report(report);
+        let results = self.detect_files_tallied(files, options, &mut tally)?;
+        Ok(with_report(WhatLangResults::Files(results), &tally, report))
+    }
+
+    fn detect_files_tallied(
+        &self,
+        files: Vec<PathBuf>,
+        options: &FileOptions,
+        tally: &mut Tally,
+    ) -> Result<Vec<WhatLangFromFileResult>, WhatLangError> {
+        let FileOptions {
+            format,
+            sections,
+            granularity,
+            distribution,
+        } = *options;
+        let mut result: Vec<WhatLangFromFileResult> = vec![];
+        let count = files.len();
+        for file in files {
+            let (buffer, sample) = match self.read_file(&file, format) {
+                Ok(b) => b,
+                Err(e) => {
+                    error!("{}. Skipping file", e.in_file(&file));
+                    continue;
+                }
+            };
+
+            let (texts, message_ids, sections) = match (format, DocumentKind::from_path(&file)) {
+                (InputFormat::Email | InputFormat::Mbox, _) => {
+                    let messages = match format {
+                        InputFormat::Email => match email::parse_message(&buffer) {
+                            Some(m) => vec![m],
+                            None => {
+                                let e = WhatLangError::InvalidEmail { path: None };
+                                error!("{}. Skipping file", e.in_file(&file));
+                                continue;
+                            }
+                        },
+                        _ => parse_mbox(&buffer, tally),
+                    };
+                    let (message_ids, texts) = messages
+                        .into_iter()
+                        .map(|m| (m.message_id, Cow::Owned(m.text)))
+                        .unzip();
+                    (texts, Some(message_ids), None)
+                }
+                (InputFormat::Plain, Some(kind)) => {
+                    let document = match extract_sections(kind, &buffer, &file) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            error!("{}. Skipping file", e);
+                            continue;
+                        }
+                    };
+                    let texts = vec![Cow::Owned(document::join_sections(&document))];
+                    let sections = sections.then(|| {
+                        document
+                            .into_iter()
+                            .map(|section| WhatLangFromSectionResult {
+                                result: self.detect(&section.text),
+                                title: section.title,
+                            })
+                            .collect()
+                    });
+                    (texts, None, sections)
+                }
+                (InputFormat::Json | InputFormat::Plain, _) => {
+                    // validated in place, so that mapped files aren't copied
+                    let text = match std::str::from_utf8(&buffer) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            error!("{}. Skipping file", WhatLangError::from(e).in_file(&file));
+                            continue;
+                        }
+                    };
+                    let texts = if format == InputFormat::Json {
+                        match validate_json(text) {
+                            Ok(texts) => texts.into_iter().map(Cow::Owned).collect(),
+                            Err(e) => {
+                                error!("{}. Skipping file", e.in_file(&file));
+                                continue;
+                            }
+                        }
+                    } else {
+                        vec![Cow::Borrowed(text)]
+                    };
+                    (texts, None, None)
+                }
+            };
+
+            let segments = granularity
+                .segment_mode()
+                .map(|mode| self.segment_many(&texts, mode));
+            // the distribution is based on sentences unless another segment mode was requested
+            let distribution = distribution.then(|| match &segments {
+                Some(segments) => segment::merge_distributions(segments),
+                None => {
+                    segment::merge_distributions(&self.segment_many(&texts, SegmentMode::Sentence))
+                }
+            });
+            let results: Vec<WhatLangResult> = texts.iter().map(|text| self.detect(text)).collect();
+            let sentences =
+                (granularity == Granularity::Sentences).then(|| self.sentences_many(&texts));
+            for (i, text) in texts.iter().enumerate() {
+                match (&segments, &sentences) {
+                    (Some(segments), _) => tally_spans(tally, segments[i].spans(), text),
+                    (_, Some(sentences)) => tally_spans(tally, &sentences[i], text),
+                    _ => tally.add(&results[i], text.chars().count()),
+                }
+            }
+            result.push(WhatLangFromFileResult {
+                file,
+                results,
+                message_ids,
+                sections,
+                sentences,
+                segments,
+                distribution,
+                sample,
+            })
+        }
+        if result.is_empty() {
+            return Err(WhatLangError::NoFileProcessed { files: count });
+        }
+        tally.skip(count - result.len());
+        Ok(result)
+    }
+
+    fn process_bytes(
+        &self,
+        buffer: Vec<u8>,
+        format: InputFormat,
+        granularity: Granularity,
+        report: Report,
+    ) -> Result<WhatLangReport, WhatLangError> {
+        let mut tally = Tally::for_report(report);
+        let results = match format {
+            InputFormat::Json => {
+                let texts = validate_json(&validate_utf8(buffer)?)?;
+                WhatLangResults::Texts(
+                    texts
+                        .iter()
+                        .map(|text| self.detect_granular(text, granularity, &mut tally))
+                        .collect(),
+                )
+            }
+            InputFormat::Plain => {
+                let text = validate_utf8(buffer)?;
+                WhatLangResults::Text(self.detect_granular(&text, granularity, &mut tally))
+            }
+            InputFormat::Email => {
+                let message = email::parse_message(&buffer)
+                    .ok_or(WhatLangError::InvalidEmail { path: None })?;
+                WhatLangResults::Message(self.detect_message(message, &mut tally))
+            }
+            InputFormat::Mbox => WhatLangResults::Messages(
+                parse_mbox(&buffer, &mut tally)
+                    .into_iter()
+                    .map(|message| self.detect_message(message, &mut tally))
+                    .collect(),
+            ),
+        };
+        Ok(with_report(results, &tally, report))
+    }
+
+    fn sentences_many(&self, texts: &[Cow<str>]) -> Vec<Vec<WhatLangSpan>> {
+        texts
+            .iter()
+            .map(|text| self.detect_sentences(text))
+            .collect()
+    }
+
+    fn segment_many(&self, texts: &[Cow<str>], mode: SegmentMode) -> Vec<WhatLangSegmentation> {
+        texts.iter().map(|text| self.segment(text, mode)).collect()
+    }
+
+    fn detect_granular(
+        &self,
+        text: &str,
+        granularity: Granularity,
+        tally: &mut Tally,
+    ) -> WhatLangFromTextResult {
+        match granularity {
+            Granularity::Text => {
+                let result = self.detect(text);
+                tally.add(&result, text.chars().count());
+                WhatLangFromTextResult::Text(result)
+            }
+            Granularity::Sentences => {
+                let sentences = self.detect_sentences(text);
+                tally_spans(tally, &sentences, text);
+                WhatLangFromTextResult::Sentences(sentences)
+            }
+            Granularity::Segments(mode) => {
+                let segmentation = self.segment(text, mode);
+                tally_spans(tally, segmentation.spans(), text);
+                WhatLangFromTextResult::Segments(segmentation)
+            }
+        }
+    }
+
+    /// Reads a file, or only a sample of it if it is a plain text file and the detector samples
+    /// files.
+    fn read_file(
+        &self,
+        path: &Path,
+        format: InputFormat,
+    ) -> Result<(FileBytes, Option<FileSample>), WhatLangError> {
+        match &self.config.sampling {
+            Some(sampling)
+                if format == InputFormat::Plain && DocumentKind::from_path(path).is_none() =>
+            {
+                let (buffer, sample) = sampling.read(path)?;
+                Ok((FileBytes::Read(buffer), sample))
+            }
+            _ => Ok((FileBytes::read(path)?, None)),
+        }
+    }
+
+    /// Reads the text of a file, extracting it from DOCX, ODT and EPUB files.
+    pub(crate) fn read_text_file(&self, path: &Path) -> Result<String, WhatLangError> {
+        let (buffer, _) = self
+            .read_file(path, InputFormat::Plain)
+            .map_err(|e| e.in_file(path))?;
+        match DocumentKind::from_path(path) {
+            Some(kind) => Ok(document::join_sections(&extract_sections(
+                kind, &buffer, path,
+            )?)),
+            None => match std::str::from_utf8(&buffer) {
+                Ok(text) => Ok(text.to_string()),
+                Err(e) => Err(WhatLangError::from(e).in_file(path)),
+            },
+        }
+    }
+
+    fn detect_message(
+        &self,
+        message: email::EmailBody,
+        tally: &mut Tally,
+    ) -> WhatLangFromMessageResult {
+        let result = self.detect(&message.text);
+        tally.add(&result, message.text.chars().count());
+        WhatLangFromMessageResult {
+            result,
+            message_id: message.message_id,
+        }
+    }
+}
+
+/// The input of a [`ProcessRequest`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessInput {
+    /// A text, as given as argument or read from stdin
+    Text(String),
+    /// Raw input that isn't valid UTF-8, e.g. an email in a legacy charset read from stdin
+    Bytes(Vec<u8>),
+    Files(Vec<PathBuf>),
+}
+
+/// The input and all options of a detection run, so that the run can be handed to a daemon.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProcessRequest {
+    pub input: ProcessInput,
+    #[serde(default)]
+    pub format: InputFormat,
+    /// Only used for files, see [`FileOptions`]
+    #[serde(default)]
+    pub sections: bool,
+    #[serde(default)]
+    pub granularity: Granularity,
+    /// Only used for files, see [`FileOptions`]
+    #[serde(default)]
+    pub distribution: bool,
+    #[serde(default)]
+    pub report: Report,
+    #[serde(default)]
+    pub detector: DetectorConfig,
+}
+
+impl ProcessRequest {
+    pub fn process(self) -> Result<WhatLangReport, WhatLangError> {
+        let detector = self.detector.build()?;
+        match self.input {
+            ProcessInput::Text(text) => {
+                detector.process_string(text, self.format, self.granularity, self.report)
+            }
+            ProcessInput::Bytes(bytes) => {
+                detector.process_bytes(bytes, self.format, self.granularity, self.report)
             }
-            false => {
-                let results = detect(&text);
-                result.push(WhatLangFromFileResult {
-                    file,
-                    results: vec![results],
-                })
+            ProcessInput::Files(files) => {
+                let options = FileOptions {
+                    format: self.format,
+                    sections: self.sections,
+                    granularity: self.granularity,
+                    distribution: self.distribution,
+                };
+                detector.process_files(files, &options, self.report)
             }
         }
     }
-    if result.is_empty() {
-        return Err("Didn't process any file due to errors".into());
+}
+
+/// Like [`LangDetector::process_string`] with the default detector.
+pub fn process_string(
+    arg: String,
+    format: InputFormat,
+    granularity: Granularity,
+    report: Report,
+) -> Result<WhatLangReport, WhatLangError> {
+    LangDetector::default().process_string(arg, format, granularity, report)
+}
+
+/// Like [`LangDetector::process_stdin`] with the default detector.
+pub fn process_stdin(
+    format: InputFormat,
+    granularity: Granularity,
+    report: Report,
+) -> Result<WhatLangReport, WhatLangError> {
+    LangDetector::default().process_stdin(format, granularity, report)
+}
+
+/// Like [`LangDetector::process_files`] with the default detector.
+pub fn process_files(
+    files: Vec<PathBuf>,
+    options: &FileOptions,
+    report: Report,
+) -> Result<WhatLangReport, WhatLangError> {
+    LangDetector::default().process_files(files, options, report)
+}
+
+/// Detects the language of a text with the default detector.
+pub fn detect_string(text: &str) -> WhatLangResult {
+    LangDetector::default().detect(text)
+}
+
+/// Like [`LangDetector::detect_json`] with the default detector.
+pub fn detect_json(json: &str) -> Result<Vec<WhatLangResult>, WhatLangError> {
+    LangDetector::default().detect_json(json)
+}
+
+/// Like [`LangDetector::detect_stdin`] with the default detector.
+pub fn detect_stdin() -> Result<WhatLangResult, WhatLangError> {
+    LangDetector::default().detect_stdin()
+}
+
+/// Like [`LangDetector::detect_stdin_json`] with the default detector.
+pub fn detect_stdin_json() -> Result<Vec<WhatLangResult>, WhatLangError> {
+    LangDetector::default().detect_stdin_json()
+}
+
+/// Like [`LangDetector::detect_files`] with the default detector.
+pub fn detect_files(
+    files: Vec<PathBuf>,
+    options: &FileOptions,
+) -> Result<Vec<WhatLangFromFileResult>, WhatLangError> {
+    LangDetector::default().detect_files(files, options)
+}
+
+impl Granularity {
+    fn segment_mode(&self) -> Option<SegmentMode> {
+        match self {
+            Granularity::Segments(mode) => Some(*mode),
+            _ => None,
+        }
+    }
+}
+
+fn with_report(results: WhatLangResults, tally: &Tally, report: Report) -> WhatLangReport {
+    match report {
+        Report::Results => WhatLangReport::Results(results),
+        Report::ResultsWithSummary => WhatLangReport::ResultsWithSummary {
+            results,
+            summary: tally.summarize(),
+        },
+        Report::Summary => WhatLangReport::Summary(tally.summarize()),
     }
-    Ok(json!(result))
 }
 
-fn validate_stdin_input() -> Result<String, Box<dyn Error>> {
+fn parse_mbox(buffer: &[u8], tally: &mut Tally) -> Vec<email::EmailBody> {
+    email::parse_mbox(buffer)
+        .into_iter()
+        .filter_map(|message| match message {
+            Ok(m) => Some(m),
+            Err(e) => {
+                error!("{}. Skipping message", e);
+                tally.skip(1);
+                None
+            }
+        })
+        .collect()
+}
+
+fn read_stdin_input() -> Result<Vec<u8>, WhatLangError> {
     let mut buffer = Vec::new();
     std::io::stdin().read_to_end(&mut buffer)?;
-    let string = String::from_utf8(buffer)?;
-    Ok(string)
+    Ok(buffer)
 }
 
-fn validate_file_input(path: &PathBuf) -> Result<String, Box<dyn Error>> {
-    let mut buffer = Vec::new();
-    File::open(path)?.read_to_end(&mut buffer)?;
+fn extract_sections(
+    kind: DocumentKind,
+    buffer: &[u8],
+    path: &Path,
+) -> Result<Vec<document::Section>, WhatLangError> {
+    document::extract_sections(kind, buffer).map_err(|e| WhatLangError::InvalidDocument {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+fn validate_utf8(buffer: Vec<u8>) -> Result<String, WhatLangError> {
     let string = String::from_utf8(buffer)?;
     Ok(string)
 }
 
-fn validate_json(string: &str) -> Result<Vec<String>, Box<dyn Error>> {
+fn validate_json(string: &str) -> Result<Vec<String>, WhatLangError> {
     let vec: Vec<String> = serde_json::from_str(string)?;
     Ok(vec)
 }
 
-fn detect(text: &str) -> WhatLangResult {
-    if let Some(info) = whatlang::detect(text) {
-        WhatLangResult::Ok(LangInfo::from_info(info))
-    } else {
-        WhatLangResult::Error("Failed to detect language".to_string())
+fn tally_spans(tally: &mut Tally, spans: &[WhatLangSpan], text: &str) {
+    for span in spans {
+        tally.add(span.result(), span.characters(text));
     }
 }
-
-fn detect_many(texts: Vec<String>) -> Vec<WhatLangResult> {
-    texts.iter().map(|text| detect(text)).collect()
-}
@@ -1,17 +1,30 @@
-use std::io::{stdout, Write};
+use std::error::Error;
+use std::io::{self, stdin, stdout, BufWriter, Read, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use clap::{Args, Parser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use log::{debug, error};
+use serde::Serialize;
+use serde_json::{json, Value};
 
-use whatlang_cli::{process_files, process_stdin, process_string};
+use whatlang_cli::{
+    benchmark, evaluate, filter_items, run_worker, serve, split_items, train_profiles,
+    BenchOptions, DetectorConfig, ErrorInfo, Granularity, InputFormat, ItemFormat, ItemOptions,
+    LabeledFormat, Method, NormalizationForm, ProcessInput, ProcessRequest, Report, SampleStrategy,
+    Sampling, SegmentMode, ServeOptions, TrainOptions, WhatLangError, WhatLangResult,
+    DEFAULT_BACKEND, DEFAULT_CHUNKS, DEFAULT_PROFILE_SIZE,
+};
+#[cfg(unix)]
+use whatlang_cli::{default_socket, request_daemon, run_daemon};
 
 /// CLI application for detecting the language of a text wrapping the amazing whatlang-rs crate.
 ///
 ///#### Input Modes
 ///
-///You can use any of the input modes `TEXT`, `--stdin` and `--file` exclusively. If you try to use multiple of them,
+///You can use any of the input modes `TEXT`, `--stdin`, `--file` and `--worker` exclusively. If you try to use multiple of them,
 ///the application will return an error.
 ///
 ///Any input mode can be combined with the `--json` flag to tell the application, that the input is a JSON array of strings.
@@ -27,6 +40,80 @@ use whatlang_cli::{process_files, process_stdin, process_string};
 ///For the combination of `--stdin` and `--json`, as well as for the combination of `TEXT` and `--json`, this holds true, as
 ///the application expects a JSON array of strings in stdin or as argument.
 ///
+///Instead of `--json`, any input mode can be combined with `--email` or `--mbox` to process a single RFC 822 message or
+///an mbox file. MIME parts and transfer encodings are decoded, `text/plain` parts are preferred over `text/html` parts
+///and quoted replies and signatures are stripped before the language of each message is detected.
+///
+///In the `--file` input mode, DOCX, ODT and EPUB files are recognized by their file extension and their text is extracted
+///directly. Add the `--sections` flag to additionally detect the language of every chapter (EPUB) or every section
+///starting at a heading (DOCX, ODT).
+///
+///The plain text and the JSON input format can be combined with `--segment <MODE>` to detect texts that contain
+///several languages. Every text is split into sentences (`sentence`), paragraphs (`paragraph`) or sliding windows of
+///`--window-size` characters starting every `--window-step` characters (`window`). The language of every segment is
+///detected and adjacent segments of the same language are merged into spans.
+///
+///The `--sentences` flag splits every plain text or JSON input at Unicode sentence boundaries, with special handling for
+///CJK punctuation, and detects every sentence on its own without merging sentences of the same language.
+///
+///In the `--file` input mode, the `--distribution` flag additionally reports the share of every language in each file,
+///weighted by character count. It is computed from the spans of all texts of a file, segmented by sentences unless
+///another mode is set with `--segment`.
+///
+///Any input mode can be combined with `--summary` to additionally report aggregate statistics over all results, or with
+///`--summary-only` to report them instead of the results. Depending on `--sentences` and `--segment`, the statistics are
+///computed over texts, sentences or spans.
+///
+///The `--stdin` input mode can be combined with `--lines` to detect every line on its own and print each result as a single
+///line of JSON as soon as it is detected. Lines that aren't valid UTF-8 yield an `invalid_utf8` error object.
+///
+///The `--worker` input mode keeps the process running and answers newline-delimited JSON-RPC 2.0 requests from stdin on
+///stdout, one response per line, until stdin is closed. The methods are `detect` (`{"text": ...}`), `detect_batch`
+///(`{"texts": [...]}`), `detect_script` (`{"text": ...}`), `options` (any of the detector options below, e.g.
+///`{"allowlist": ["deu", "eng"]}`, changes the detector of the following requests and returns its options) and `process` (the
+///input and options of a whole run, returns the same JSON as the CLI). Invalid requests, including lines that aren't valid
+///UTF-8, are answered with a JSON-RPC error and don't stop the worker. The session starts with the detector options given on the command line.
+///
+///On Unix, `whatlang-cli daemon` shares one detector process between local tools. It listens on a Unix socket
+///(`$XDG_RUNTIME_DIR/whatlang-cli.sock` by default) that only its user can connect to and speaks the protocol of
+///`--worker` on every connection, whose `process` method runs a whole detection. Add `--connect [SOCKET]` to any input mode
+///to hand the detection to a running daemon. If no daemon is running, the input is detected in-process.
+///
+///#### Detector Options
+///
+///The detector options apply to every input mode and to the `split`, `filter`, `eval`, `bench` and `serve` subcommands. `--backend`
+///chooses the backend that detects the languages, `whatlang` (the default) or `profiles`, and `--method` the detection method
+///of whatlang (`combined`, `trigram` or `alphabet`). `--allowlist` restricts detection to the given ISO 639-3 codes, `--denylist` excludes them. Results below `--min-confidence` and, with `--reliable-only`, unreliable
+///results are rejected with a `below_threshold` error.
+///
+///Texts are normalized before detection by these steps, in this order: `--normalize nfc|nfkc` converts them to a Unicode
+///normalization form, `--strip-urls` removes URLs and email addresses, `--strip-mentions` @mentions, `--strip-hashtags`
+///#hashtags, `--strip-numbers` numbers and `--strip-emoji` emoji, `--lowercase` lowercases them and
+///`--collapse-whitespace` replaces runs of whitespace by a single space. If any step is enabled, the result additionally
+///contains the number of `normalized_characters` that remained.
+///
+///With `--ensemble`, every voter of `--voters` (backends, each with an optional weight, e.g. `whatlang-trigram=2,profiles`)
+///detects the text and the language with the largest sum of weights wins. The backends `whatlang-combined`,
+///`whatlang-trigram` and `whatlang-alphabet` detect with a single method of whatlang and are the default voters. The result additionally
+///contains the `agreement`, the share of the weight that voted for the language, and the individual `votes`.
+///
+///`--profiles` loads profiles trained with the `train` subcommand. Texts that the backend detects as a language with a
+///profile of its own are detected again with the profiles, and a custom language of the best profile, e.g. Swiss German,
+///replaces the result. Results of custom languages additionally contain their `code`, which `--allowlist` and
+///`--denylist` accept as well. With `--backend profiles`, the profiles alone detect every text.
+///
+///With `--composition`, every result, including error results, additionally contains the `composition` of its text: the
+///number of `characters`, the letters of every script with their `ratio` among all letters and the number of `digits`,
+///`punctuation` characters and `emoji`.
+///
+///With `--min-chars` and `--min-words`, texts with fewer characters other than whitespace or fewer words, counted after
+///normalization, aren't detected. Their result is `TooShort` with the `characters` and `words` of the text instead.
+///
+///With `--max-bytes`, only a sample of every plain text file larger than the limit is read: its beginning
+///(`--sample head`), `--chunks` evenly spaced chunks (`--sample spread`) or chunks at random offsets chosen by `--seed`
+///(`--sample random`). The result of a sampled file additionally contains the `sample` that was read. Sampling only
+///applies to files, so `--max-bytes` can't be combined with `TEXT` or `--stdin`.
+///
 ///#### Output
 ///
 ///If the application returns with exit code 0 which means it did process the input data successfully, it will print
@@ -44,10 +131,14 @@ use whatlang_cli::{process_files, process_stdin, process_string};
 ///}
 ///```
 ///
-///If language detection fails, the single `Result` JSON object will contain an error message instead of the language detection result:
+///If language detection fails, the single `Result` JSON object will contain an error object with a machine-readable `code`
+///and a `message` instead of the language detection result:
 ///```json
 ///{
-///    "Error": "Failed to detect language"
+///    "Error": {
+///        "code": "detection_failed",
+///        "message": "Failed to detect language"
+///    }
 ///}
 ///```
 ///
@@ -103,6 +194,62 @@ use whatlang_cli::{process_files, process_stdin, process_string};
 ///]
 ///```
 ///
+///If the `--email` or `--mbox` flag is set, the result of every message is reported together with its `Message-ID`.
+///For the `TEXT` and the `--stdin` input mode, `--email` outputs a single object and `--mbox` an array of objects:
+///```json
+///{
+///  "message_id": "string | null",
+///  "result": {
+///    "Ok": {
+///      "confidence": "float [0,1]",
+///      "is_reliable": "bool",
+///      "language": "string",
+///      "script": "string"
+///    }
+///  }
+///}
+///```
+///For the `--file` input mode, the results of all messages of a file are listed in `results` and the `Message-ID`s
+///in the same order in `message_ids`.
+///
+///If the `--sections` flag is set, the result of a DOCX, ODT or EPUB file additionally contains a `sections` array with
+///the `title` and the `result` of every chapter or section.
+///
+///If `--segment` is set, the `TEXT` and the `--stdin` input mode output the `spans` with their byte offsets and the
+///`distribution` of the languages weighted by character count instead of a single `Result` JSON object (or an array of
+///them for `--json`). A `language` of `null` in the distribution stands for text whose language couldn't be detected.
+///For the `--file` input mode, these objects are listed in `segments` in the same order as `results`.
+///
+///If `--sentences` is set, the output contains an array of `Result` JSON objects with the byte offsets (`start`, `end`)
+///of their sentence instead of a single `Result` JSON object. For the `--file` input mode, these arrays are listed in
+///`sentences` in the same order as `results`.
+///
+///If `--distribution` is set, every file result additionally contains a `distribution` array with the share of every
+///language, ordered from the largest share to the smallest.
+///
+///If `--summary` is set, the output is an object with the `results` of the chosen input mode and their `summary`. If
+///`--summary-only` is set, the output is the summary alone. It contains the number of `items`, `characters`, `errors`,
+///`too_short` items and `skipped` files or messages, the `reliability_ratio` and per-language and per-script statistics.
+///
+///#### Subcommands
+///
+///The `split` subcommand partitions lines, JSONL records or files into one bucket per language. See
+///`whatlang-cli help split` for details. The `filter` subcommand passes through only the lines, JSONL records or files
+///of the given languages. See `whatlang-cli help filter` for details. The `eval` subcommand detects the languages of
+///labeled examples from JSONL or CSV files and reports accuracy, per-language precision, recall and F1 and a confusion
+///matrix. See `whatlang-cli help eval` for details. The `train` subcommand builds trigram profiles of the languages of
+///labeled examples for `--profiles`. See `whatlang-cli help train` for details. The `bench` subcommand measures texts/sec,
+///MB/sec and latency percentiles of detection on a corpus. See `whatlang-cli help bench` for details. The `serve` subcommand
+///serves a JSON API over HTTP. See `whatlang-cli help serve` for details.
+///
+///#### Exit Codes
+///
+///The application returns with exit code 0 on success, 2 for invalid arguments and otherwise with an exit code for the
+///kind of error: 3 (reading or writing a file, stdin or stdout failed), 4 (invalid UTF-8), 5 (invalid JSON or CSV),
+///6 (invalid email), 7 (invalid document), 8 (no file or text could be processed), 10 (invalid detector options),
+///11 (the server or daemon couldn't listen) or 1 (any other error). Texts whose language can't be detected are reported
+///in the output and don't change the exit code.
+///
 ///#### Logging
 ///The application uses the [`env_logger`](https://github.com/rust-cli/env_logger) crate for logging. You can set the log
 ///level by setting the `RUST_LOG` environment variable, e.g. `export RUST_LOG=debug`. The application will allways log to stderr.
@@ -114,64 +261,937 @@ use whatlang_cli::{process_files, process_stdin, process_string};
 /// https://github.com/greyblake/whatlang-rs
 ///
 #[derive(Parser)]
-#[command(version, about, verbatim_doc_comment)]
+#[command(
+    version,
+    about,
+    verbatim_doc_comment,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[command(flatten)]
     input: Input,
 
-    /// Process input as a JSON array of strings.
-    #[arg(long, short)]
-    json: bool,
+    #[command(flatten)]
+    format: Format,
+
+    #[command(flatten)]
+    detector: DetectorArgs,
+
+    /// Additionally report results per chapter or section of DOCX, ODT and EPUB files.
+    #[arg(long, requires = "file")]
+    sections: bool,
+
+    /// Split texts into segments, detect each segment and report the spans of every language.
+    #[arg(long, value_name = "MODE", conflicts_with_all = ["email", "mbox"])]
+    segment: Option<SegmentBy>,
+
+    /// Detect every sentence on its own and report the results with their offsets.
+    #[arg(long, conflicts_with_all = ["email", "mbox", "segment"])]
+    sentences: bool,
+
+    /// Additionally report the share of every language in each file, weighted by character count.
+    #[arg(long, requires = "file")]
+    distribution: bool,
+
+    /// Additionally report aggregate statistics over all results.
+    #[arg(long)]
+    summary: bool,
+
+    /// Report aggregate statistics over all results instead of the results.
+    #[arg(long, conflicts_with = "summary")]
+    summary_only: bool,
+
+    /// The number of characters of a window in the `window` segment mode.
+    #[arg(long, default_value_t = 200)]
+    window_size: usize,
+
+    /// The number of characters between the start of two windows in the `window` segment mode.
+    #[arg(long, default_value_t = 100)]
+    window_step: usize,
+
+    /// Hand the input to the daemon listening on this socket (`$XDG_RUNTIME_DIR/whatlang-cli.sock`
+    /// by default), detecting in-process if none is running.
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "SOCKET",
+        num_args = 0..=1,
+        conflicts_with_all = ["worker", "lines"]
+    )]
+    connect: Option<Option<PathBuf>>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write every item into a bucket per language
+    ///
+    /// Lines and JSONL records are written into `<OUT_DIR>/<CODE>.jsonl`, files are copied into
+    /// `<OUT_DIR>/<CODE>/`, where `<CODE>` is the ISO 639-3 code of the language. Undetected items
+    /// and, with `--reliable-only`, unreliable items go into the `und` bucket. Files whose name is
+    /// already taken in their bucket are skipped.
+    Split {
+        #[command(flatten)]
+        items: ItemArgs,
+
+        /// The directory to write the buckets into
+        #[arg(long, short)]
+        out_dir: PathBuf,
+
+        /// Symlink files into the buckets instead of copying them
+        #[arg(long)]
+        symlink: bool,
+    },
+    /// Pass through only the items of the given languages
+    ///
+    /// Matching lines, JSONL records or file paths are written unchanged to stdout.
+    Filter {
+        #[command(flatten)]
+        items: ItemArgs,
+
+        /// The ISO 639-3 codes of the languages to pass through, e.g. `deu`. `und` matches
+        /// undetected and, with `--reliable-only`, unreliable items. Defaults to all detected languages
+        #[arg(long, short, value_delimiter = ',')]
+        lang: Vec<String>,
+
+        /// Pass through the items that don't match instead
+        #[arg(long, short = 'v')]
+        invert: bool,
+    },
+    /// Measure detection against examples labeled with their expected language
+    ///
+    /// Every example is detected under the given thresholds and compared with its label. Labels
+    /// may be ISO 639-3 codes or English language names. Reports accuracy, precision, recall and
+    /// F1 per language and a confusion matrix.
+    Eval {
+        #[command(flatten)]
+        examples: LabeledArgs,
+
+        #[command(flatten)]
+        detector: DetectorArgs,
+
+        /// How the report is printed
+        #[arg(long, value_enum, default_value_t = OutputArg::Text)]
+        output: OutputArg,
+    },
+    /// Build trigram profiles of the languages of labeled examples
+    ///
+    /// Every label gets a profile of the most frequent trigrams of its examples, e.g. for
+    /// languages or dialects that whatlang doesn't know. Load the profiles with `--profiles` to
+    /// detect their languages alongside whatlang. Labels are the codes reported for the
+    /// languages, ISO 639-3 codes or English names of languages that whatlang knows are mapped to
+    /// their codes. Other labels may only contain ASCII letters, digits, `_` and `-`.
+    Train {
+        #[command(flatten)]
+        examples: LabeledArgs,
+
+        /// The file to write the profiles into
+        #[arg(long, short)]
+        out_file: PathBuf,
+
+        /// The name reported for a language, e.g. `gsw=Swiss German`. Defaults to the English
+        /// name of languages that whatlang knows and to the label otherwise
+        #[arg(long, value_name = "CODE=NAME", value_parser = parse_name)]
+        name: Vec<(String, String)>,
+
+        /// The number of most frequent trigrams kept per language
+        #[arg(long, default_value_t = DEFAULT_PROFILE_SIZE)]
+        size: usize,
+    },
+    /// Measure the detection throughput on a corpus
+    ///
+    /// Every text of the corpus is detected `--iterations` times with the detector options, once
+    /// per method of `--methods`. Reports texts/sec, MB/sec and latency percentiles for the whole
+    /// corpus and per text-length bucket.
+    Bench {
+        #[command(flatten)]
+        items: ItemArgs,
+
+        /// How often every text is detected
+        #[arg(long, default_value_t = 3)]
+        iterations: usize,
+
+        /// The upper bounds in characters of the text-length buckets
+        #[arg(long, value_delimiter = ',', default_values_t = [64, 256, 1024])]
+        buckets: Vec<usize>,
+
+        /// Measure each of these methods of whatlang instead of `--method`, e.g.
+        /// `combined,trigram,alphabet`
+        #[arg(long, value_enum, value_delimiter = ',')]
+        methods: Vec<MethodArg>,
+
+        /// How the report is printed
+        #[arg(long, value_enum, default_value_t = OutputArg::Text)]
+        output: OutputArg,
+    },
+    /// Serve a JSON API over HTTP
+    ///
+    /// `POST /detect` takes a JSON string and returns its result, `POST /detect/batch` takes a JSON
+    /// array of strings and returns an array of results. `GET /health` and `GET /ready` report
+    /// whether the server is alive and accepts requests. On SIGINT or SIGTERM, `GET /ready` returns
+    /// 503 and the server answers requests until none arrived for a moment before exiting.
+    Serve {
+        /// The address to listen on
+        #[arg(long, short, default_value = "127.0.0.1:8080")]
+        address: String,
+
+        /// Reject requests whose body is larger than this number of bytes
+        #[arg(long, default_value_t = 1024 * 1024)]
+        max_body_bytes: usize,
+
+        /// The number of requests handled concurrently. Defaults to the number of CPUs
+        #[arg(long)]
+        workers: Option<usize>,
+
+        #[command(flatten)]
+        detector: DetectorArgs,
+    },
+    /// Share one detector process between local tools over a Unix socket
+    ///
+    /// Every connection speaks the newline-delimited JSON-RPC protocol of `--worker`. Run the
+    /// usual detection with `--connect` to hand it to the daemon. On SIGINT or SIGTERM, the
+    /// daemon removes its socket and exits.
+    #[cfg(unix)]
+    Daemon {
+        /// The socket to listen on, `$XDG_RUNTIME_DIR/whatlang-cli.sock` by default
+        #[arg(long, short)]
+        socket: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct ItemArgs {
+    #[command(flatten)]
+    corpus: CorpusArgs,
+
+    #[command(flatten)]
+    detector: DetectorArgs,
+}
+
+#[derive(Args)]
+struct LabeledArgs {
+    /// The files to read labeled examples from. Without inputs, examples are read from stdin
+    #[arg()]
+    inputs: Vec<PathBuf>,
+
+    /// How examples are read
+    #[arg(long, value_enum, default_value_t = LabeledFormatArg::Jsonl)]
+    format: LabeledFormatArg,
+
+    /// The JSONL field or CSV column that contains the text
+    #[arg(long, default_value = "text")]
+    text_field: String,
+
+    /// The JSONL field or CSV column that contains the expected language
+    #[arg(long, default_value = "lang")]
+    label_field: String,
+}
+
+impl LabeledArgs {
+    fn format(&self) -> LabeledFormat {
+        match self.format {
+            LabeledFormatArg::Jsonl => LabeledFormat::Jsonl,
+            LabeledFormatArg::Csv => LabeledFormat::Csv,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CorpusArgs {
+    /// The files to read items from, or the items themselves for `--format files`. Without
+    /// inputs, items are read line by line from stdin
+    #[arg()]
+    inputs: Vec<PathBuf>,
+
+    /// How items are read
+    #[arg(long, value_enum, default_value_t = ItemFormatArg::Lines)]
+    format: ItemFormatArg,
+
+    /// The field of a JSONL object that contains the text
+    #[arg(long, default_value = "text")]
+    field: String,
+}
+
+#[derive(Args)]
+struct DetectorArgs {
+    /// The backend that detects the languages: `whatlang` or `profiles`, the profiles of
+    /// `--profiles` alone
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_BACKEND)]
+    backend: String,
+
+    /// The detection method of the whatlang backend
+    #[arg(long, value_enum, default_value_t = MethodArg::Combined)]
+    method: MethodArg,
+
+    /// Only detect the languages with these ISO 639-3 codes, e.g. `deu,eng`
+    #[arg(
+        long,
+        value_name = "CODES",
+        value_delimiter = ',',
+        conflicts_with = "denylist"
+    )]
+    allowlist: Vec<String>,
+
+    /// Never detect the languages with these ISO 639-3 codes
+    #[arg(long, value_name = "CODES", value_delimiter = ',')]
+    denylist: Vec<String>,
+
+    /// Detect with every voter of `--voters` and combine their weighted votes
+    #[arg(long)]
+    ensemble: bool,
+
+    /// The voters of `--ensemble`: backends, each with an optional weight, e.g.
+    /// `whatlang-trigram=2,profiles`. Defaults to the three methods of whatlang
+    #[arg(
+        long,
+        value_name = "VOTERS",
+        value_delimiter = ',',
+        value_parser = parse_voter,
+        default_values = ["whatlang-combined", "whatlang-trigram", "whatlang-alphabet"],
+        requires = "ensemble"
+    )]
+    voters: Vec<(String, f64)>,
+
+    /// Also detect the languages of profiles trained with `train`
+    #[arg(long, value_name = "FILE")]
+    profiles: Vec<PathBuf>,
+
+    /// Convert texts to this Unicode normalization form before detection
+    #[arg(long, value_name = "FORM", value_enum, default_value_t = NormalizationFormArg::None)]
+    normalize: NormalizationFormArg,
+
+    /// Lowercase texts before detection
+    #[arg(long)]
+    lowercase: bool,
+
+    /// Remove URLs and email addresses before detection
+    #[arg(long)]
+    strip_urls: bool,
+
+    /// Remove @mentions before detection
+    #[arg(long)]
+    strip_mentions: bool,
+
+    /// Remove #hashtags before detection
+    #[arg(long)]
+    strip_hashtags: bool,
+
+    /// Remove emoji before detection
+    #[arg(long)]
+    strip_emoji: bool,
+
+    /// Remove numbers, e.g. `2024` or `10%`, before detection
+    #[arg(long)]
+    strip_numbers: bool,
+
+    /// Replace runs of whitespace by a single space before detection
+    #[arg(long)]
+    collapse_whitespace: bool,
+
+    #[command(flatten)]
+    thresholds: Thresholds,
+
+    /// Report texts with fewer characters other than whitespace as too short instead of detecting
+    /// them
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    min_chars: usize,
+
+    /// Report texts with fewer words as too short instead of detecting them
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    min_words: usize,
+
+    /// Report the letters of every script and the digits, punctuation and emoji of every text
+    #[arg(long)]
+    composition: bool,
+
+    /// Read at most this many bytes of every plain text file of `--file`
+    #[arg(long, value_name = "BYTES")]
+    max_bytes: Option<usize>,
+
+    /// Which bytes of a file larger than `--max-bytes` are read
+    #[arg(long, value_enum, default_value_t = SampleArg::Head, requires = "max_bytes")]
+    sample: SampleArg,
+
+    /// The number of chunks the `spread` and `random` samples are read in
+    #[arg(long, default_value_t = DEFAULT_CHUNKS, requires = "max_bytes")]
+    chunks: usize,
+
+    /// The seed of the `random` sample
+    #[arg(long, default_value_t = 0, requires = "max_bytes")]
+    seed: u64,
+}
+
+impl DetectorArgs {
+    fn config(&self) -> DetectorConfig {
+        DetectorConfig::new()
+            .backend(&self.backend)
+            .method(self.method.method())
+            .allowlist(&self.allowlist)
+            .denylist(&self.denylist)
+            .min_confidence(self.thresholds.min_confidence)
+            .reliable_only(self.thresholds.reliable_only)
+            .normalize(self.normalize.form())
+            .lowercase(self.lowercase)
+            .strip_urls(self.strip_urls)
+            .strip_mentions(self.strip_mentions)
+            .strip_hashtags(self.strip_hashtags)
+            .strip_emoji(self.strip_emoji)
+            .strip_numbers(self.strip_numbers)
+            .collapse_whitespace(self.collapse_whitespace)
+            .ensemble(if self.ensemble {
+                self.voters.clone()
+            } else {
+                vec![]
+            })
+            .profiles(&self.profiles)
+            .composition(self.composition)
+            .min_chars(self.min_chars)
+            .min_words(self.min_words)
+            .sampling(self.max_bytes.map(|max_bytes| {
+                Sampling::new(max_bytes)
+                    .strategy(self.sample.strategy())
+                    .chunks(self.chunks)
+                    .seed(self.seed)
+            }))
+    }
+}
+
+/// Parses a voter of an ensemble with an optional weight, e.g. `whatlang=2`.
+fn parse_voter(voter: &str) -> Result<(String, f64), String> {
+    match voter.split_once('=') {
+        Some((name, weight)) => match weight.parse() {
+            Ok(weight) => Ok((name.to_string(), weight)),
+            Err(_) => Err(format!("invalid weight '{}'", weight)),
+        },
+        None => Ok((voter.to_string(), 1.0)),
+    }
+}
+
+/// Parses the name of a language by its code, e.g. `gsw=Swiss German`.
+fn parse_name(name: &str) -> Result<(String, String), String> {
+    match name.split_once('=') {
+        Some((code, name)) => Ok((code.to_string(), name.to_string())),
+        None => Err("expected CODE=NAME".to_string()),
+    }
+}
+
+#[derive(Args)]
+struct Thresholds {
+    /// Reject results below this confidence
+    #[arg(long, default_value_t = 0.0)]
+    min_confidence: f64,
+
+    /// Reject unreliable results
+    #[arg(long)]
+    reliable_only: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ItemFormatArg {
+    /// Every line is a plain text
+    Lines,
+    /// Every line is a JSON string or a JSON object with the text in `--field`
+    Jsonl,
+    /// Every item is the path of a file
+    Files,
+}
+
+impl ItemArgs {
+    fn options(&self) -> Result<ItemOptions, WhatLangError> {
+        Ok(ItemOptions {
+            detector: self.detector.config().build()?,
+            ..self.corpus.options()
+        })
+    }
+}
+
+impl CorpusArgs {
+    fn options(&self) -> ItemOptions {
+        ItemOptions {
+            format: match self.format {
+                ItemFormatArg::Lines => ItemFormat::Lines,
+                ItemFormatArg::Jsonl => ItemFormat::Jsonl,
+                ItemFormatArg::Files => ItemFormat::Files,
+            },
+            field: self.field.clone(),
+            ..ItemOptions::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MethodArg {
+    /// Trigrams for Latin and Cyrillic scripts, alphabets for the others
+    Combined,
+    /// Trigram frequencies only
+    Trigram,
+    /// Characters of the alphabets only
+    Alphabet,
+}
+
+impl MethodArg {
+    fn method(self) -> Method {
+        match self {
+            MethodArg::Combined => Method::Combined,
+            MethodArg::Trigram => Method::Trigram,
+            MethodArg::Alphabet => Method::Alphabet,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum NormalizationFormArg {
+    /// Detect texts as they are
+    None,
+    /// Canonical composition
+    Nfc,
+    /// Compatibility composition, which additionally folds e.g. fullwidth letters and ligatures
+    Nfkc,
+}
+
+impl NormalizationFormArg {
+    fn form(self) -> NormalizationForm {
+        match self {
+            NormalizationFormArg::None => NormalizationForm::None,
+            NormalizationFormArg::Nfc => NormalizationForm::Nfc,
+            NormalizationFormArg::Nfkc => NormalizationForm::Nfkc,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SampleArg {
+    /// The beginning of the file
+    Head,
+    /// Chunks spread evenly from the beginning to the end of the file
+    Spread,
+    /// Chunks at random offsets, the same for the same `--seed`
+    Random,
+}
+
+impl SampleArg {
+    fn strategy(self) -> SampleStrategy {
+        match self {
+            SampleArg::Head => SampleStrategy::Head,
+            SampleArg::Spread => SampleStrategy::Spread,
+            SampleArg::Random => SampleStrategy::Random,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LabeledFormatArg {
+    /// Every line is a JSON object with the text and the label in fields
+    Jsonl,
+    /// A CSV file whose header names the text and label columns
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputArg {
+    /// A plain text report with tables
+    Text,
+    /// A JSON object
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SegmentBy {
+    /// Split at Unicode sentence boundaries
+    Sentence,
+    /// Split at blank lines
+    Paragraph,
+    /// Detect sliding windows of `--window-size` characters
+    Window,
 }
 
 #[derive(Args)]
 #[group(required = true, multiple = false)]
 struct Input {
     /// The text that you want to detect the language of
-    #[arg()]
+    #[arg(conflicts_with = "max_bytes")]
     text: Option<String>,
 
     /// Get input from stdin
-    #[arg(long, short)]
+    #[arg(long, short, conflicts_with = "max_bytes")]
     stdin: bool,
 
     /// Get input from one or multiple files
     #[arg(long, short, action)]
     file: Vec<PathBuf>,
+
+    /// Keep running and answer newline-delimited JSON-RPC requests from stdin on stdout
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "email", "mbox", "segment", "sentences", "summary", "summary_only"]
+    )]
+    worker: bool,
+}
+
+#[derive(Args)]
+#[group(required = false, multiple = false)]
+struct Format {
+    /// Process input as a JSON array of strings.
+    #[arg(long, short)]
+    json: bool,
+
+    /// Process input as a single RFC 822 email message.
+    #[arg(long, short)]
+    email: bool,
+
+    /// Process input as an mbox file containing multiple email messages.
+    #[arg(long, short)]
+    mbox: bool,
+
+    /// Process every line of stdin as a text and print each result on its own line as soon as it is detected.
+    #[arg(
+        long,
+        conflicts_with_all = ["text", "file", "worker", "segment", "sentences", "summary", "summary_only"]
+    )]
+    lines: bool,
+}
+
+impl Format {
+    fn input_format(&self) -> InputFormat {
+        if self.json {
+            InputFormat::Json
+        } else if self.email {
+            InputFormat::Email
+        } else if self.mbox {
+            InputFormat::Mbox
+        } else {
+            InputFormat::Plain
+        }
+    }
+}
+
+impl Cli {
+    fn granularity(&self) -> Granularity {
+        match self.segment {
+            Some(SegmentBy::Sentence) => Granularity::Segments(SegmentMode::Sentence),
+            Some(SegmentBy::Paragraph) => Granularity::Segments(SegmentMode::Paragraph),
+            Some(SegmentBy::Window) => Granularity::Segments(SegmentMode::Window {
+                size: self.window_size,
+                step: self.window_step,
+            }),
+            None if self.sentences => Granularity::Sentences,
+            None => Granularity::Text,
+        }
+    }
+
+    fn report(&self) -> Report {
+        if self.summary_only {
+            Report::Summary
+        } else if self.summary {
+            Report::ResultsWithSummary
+        } else {
+            Report::Results
+        }
+    }
 }
 
 fn main() -> ExitCode {
     let cli = Cli::parse();
     env_logger::init();
 
-    let format = if cli.json { "JSON" } else { "plain text" };
+    let result: Result<(), Box<dyn Error>> = match cli.command {
+        Some(Command::Split {
+            items,
+            out_dir,
+            symlink,
+        }) => {
+            debug!("Splitting items into {:?}", out_dir);
+            items
+                .options()
+                .and_then(|options| split_items(items.corpus.inputs, &options, &out_dir, symlink))
+                .and_then(|report| print_json(&report))
+                .map_err(Into::into)
+        }
+        Some(Command::Filter {
+            items,
+            lang,
+            invert,
+        }) => {
+            debug!("Filtering items by languages {:?}", lang);
+            let mut out = BufWriter::new(stdout().lock());
+            items
+                .options()
+                .and_then(|options| {
+                    filter_items(items.corpus.inputs, &options, &lang, invert, &mut out)
+                })
+                .map(|written| debug!("Passed through {} items", written))
+                .map_err(Into::into)
+        }
+        Some(Command::Eval {
+            examples,
+            detector,
+            output,
+        }) => {
+            debug!("Evaluating labeled examples");
+            let format = examples.format();
+            detector
+                .config()
+                .build()
+                .and_then(|detector| {
+                    let options = ItemOptions {
+                        field: examples.text_field,
+                        detector,
+                        ..ItemOptions::default()
+                    };
+                    evaluate(examples.inputs, format, &examples.label_field, &options)
+                })
+                .and_then(|evaluation| match output {
+                    OutputArg::Text => {
+                        print!("{}", evaluation);
+                        Ok(())
+                    }
+                    OutputArg::Json => print_json(&evaluation),
+                })
+                .map_err(Into::into)
+        }
+        Some(Command::Train {
+            examples,
+            out_file,
+            name,
+            size,
+        }) => {
+            debug!("Training profiles into {:?}", out_file);
+            let options = TrainOptions {
+                text_field: examples.text_field.clone(),
+                size,
+                names: name.into_iter().collect(),
+            };
+            let format = examples.format();
+            train_profiles(examples.inputs, format, &examples.label_field, &options)
+                .and_then(|profiles| {
+                    profiles.save(&out_file)?;
+                    let overview: Vec<Value> = profiles
+                        .profiles()
+                        .iter()
+                        .map(|profile| {
+                            json!({
+                                "code": profile.code(),
+                                "name": profile.name(),
+                                "script": profile.script(),
+                                "examples": profile.examples(),
+                                "trigrams": profile.trigrams().len(),
+                            })
+                        })
+                        .collect();
+                    print_json(&overview)
+                })
+                .map_err(Into::into)
+        }
+        Some(Command::Bench {
+            items,
+            iterations,
+            buckets,
+            methods,
+            output,
+        }) => {
+            debug!("Benchmarking the corpus");
+            let options = BenchOptions {
+                iterations,
+                buckets,
+                methods: methods.into_iter().map(MethodArg::method).collect(),
+            };
+            items
+                .options()
+                .and_then(|item_options| {
+                    benchmark(items.corpus.inputs.clone(), &item_options, &options)
+                })
+                .and_then(|benchmark| match output {
+                    OutputArg::Text => {
+                        print!("{}", benchmark);
+                        Ok(())
+                    }
+                    OutputArg::Json => print_json(&benchmark),
+                })
+                .map_err(Into::into)
+        }
+        Some(Command::Serve {
+            address,
+            max_body_bytes,
+            workers,
+            detector,
+        }) => {
+            let defaults = ServeOptions::default();
+            detector
+                .config()
+                .build()
+                .map_err(Into::into)
+                .and_then(|detector| {
+                    let options = ServeOptions {
+                        address,
+                        max_body_bytes,
+                        workers: workers.unwrap_or(defaults.workers),
+                        detector,
+                    };
+                    shutdown_flag().and_then(|shutdown| Ok(serve(&options, shutdown)?))
+                })
+        }
+        #[cfg(unix)]
+        Some(Command::Daemon { socket }) => match socket.or_else(default_socket) {
+            Some(socket) => shutdown_flag().and_then(|shutdown| Ok(run_daemon(&socket, shutdown)?)),
+            None => Err("XDG_RUNTIME_DIR isn't set, give the socket with --socket".into()),
+        },
+        None if cli.input.worker => {
+            debug!("Running as worker");
+            cli.detector
+                .config()
+                .build()
+                .and_then(|detector| run_worker(stdin().lock(), stdout().lock(), detector))
+                .map_err(Into::into)
+        }
+        None if cli.format.lines => {
+            debug!("Processing stdin line by line");
+            detect_lines(&cli)
+        }
+        None => detect(cli).and_then(|results| Ok(print_json(&results)?)),
+    };
+
+    if let Err(e) = result {
+        error!("{}", e);
+        return exit_code(e.as_ref());
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Maps every kind of library error to its own exit code by its code, so that errors a daemon
+/// reports exit like the same errors in-process. Other errors exit with 1, invalid arguments with 2.
+fn exit_code(e: &(dyn Error + 'static)) -> ExitCode {
+    let Some(e) = e.downcast_ref::<WhatLangError>() else {
+        return ExitCode::FAILURE;
+    };
+    match e.code() {
+        "io" | "write" => ExitCode::from(3),
+        "invalid_utf8" => ExitCode::from(4),
+        "invalid_json" | "invalid_csv" => ExitCode::from(5),
+        "invalid_email" => ExitCode::from(6),
+        "invalid_document" => ExitCode::from(7),
+        "no_file_processed" | "no_texts" => ExitCode::from(8),
+        "invalid_config" => ExitCode::from(10),
+        "listen" => ExitCode::from(11),
+        // detection errors are reported in the results and never end the run
+        _ => ExitCode::FAILURE,
+    }
+}
 
-    let result = if cli.input.stdin {
+/// Returns a flag that is set on SIGINT or SIGTERM.
+fn shutdown_flag() -> Result<Arc<AtomicBool>, Box<dyn Error>> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let handler_shutdown = Arc::clone(&shutdown);
+    ctrlc::set_handler(move || {
+        debug!("Received shutdown signal");
+        handler_shutdown.store(true, Ordering::SeqCst);
+    })?;
+    Ok(shutdown)
+}
+
+fn print_json(value: &impl Serialize) -> Result<(), WhatLangError> {
+    debug!("Finished processing, printing results");
+    let mut out = stdout().lock();
+    serde_json::to_writer_pretty(&mut out, value)
+        .map_err(io::Error::from)
+        .and_then(|_| out.write_all("\n".as_bytes()))
+        .map_err(|source| WhatLangError::Write { path: None, source })
+}
+
+/// Prints the result of every line of stdin as a single line of JSON. Lines that aren't valid UTF-8
+/// are reported as error results so that every output line belongs to the input line of the same
+/// number.
+fn detect_lines(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    let detector = cli.detector.config().build()?;
+    let mut out = stdout().lock();
+    for result in detector.detect_lines(stdin().lock()) {
+        let result = match result {
+            Ok(result) => result,
+            Err(e @ WhatLangError::Io { .. }) => return Err(e.into()),
+            Err(e) => WhatLangResult::Error(ErrorInfo::from(&e)),
+        };
+        serde_json::to_writer(&mut out, &json!(result))
+            .map_err(io::Error::from)
+            .and_then(|_| out.write_all(b"\n"))
+            .map_err(|source| WhatLangError::Write { path: None, source })?;
+    }
+    Ok(())
+}
+
+fn detect(cli: Cli) -> Result<Value, Box<dyn Error>> {
+    let input_format = cli.format.input_format();
+    let format = match input_format {
+        InputFormat::Plain => "plain text",
+        InputFormat::Json => "JSON",
+        InputFormat::Email => "email",
+        InputFormat::Mbox => "mbox",
+    };
+
+    let input = if cli.input.stdin {
         debug!("Processing stdin as {}", format);
-        process_stdin(cli.json)
+        let mut buffer = Vec::new();
+        stdin()
+            .read_to_end(&mut buffer)
+            .map_err(WhatLangError::from)?;
+        match String::from_utf8(buffer) {
+            Ok(text) => ProcessInput::Text(text),
+            Err(e) => ProcessInput::Bytes(e.into_bytes()),
+        }
     } else if !cli.input.file.is_empty() {
         debug!("Processing files {:?} as {}", cli.input.file, format);
-        process_files(cli.input.file, cli.json)
+        ProcessInput::Files(cli.input.file.clone())
     } else {
         // safe unwrap because if the program hits this branch, text arg must be there
-        let text = cli.input.text.unwrap();
+        let text = cli.input.text.clone().unwrap();
         debug!("Processing argument '{}' as {}", text, format);
-        process_string(text, cli.json)
+        ProcessInput::Text(text)
+    };
+    let mut request = ProcessRequest {
+        input,
+        format: input_format,
+        sections: cli.sections,
+        granularity: cli.granularity(),
+        distribution: cli.distribution,
+        report: cli.report(),
+        detector: cli.detector.config(),
     };
+    // fail early on an invalid config, before handing it to a daemon
+    request.detector.build()?;
 
-    if let Err(e) = result {
-        error!("{}", e);
-        return ExitCode::FAILURE;
+    #[cfg(unix)]
+    if let Some(socket) = cli
+        .connect
+        .clone()
+        .and_then(|socket| socket.or_else(default_socket))
+    {
+        // the daemon may run in another working directory
+        let absolute = |path: &PathBuf| {
+            std::path::absolute(path).map_err(|source| WhatLangError::Io {
+                path: Some(path.clone()),
+                source,
+            })
+        };
+        if let ProcessInput::Files(files) = &mut request.input {
+            for file in files.iter_mut() {
+                *file = absolute(file)?;
+            }
+        }
+        let profiles: Vec<PathBuf> = cli
+            .detector
+            .profiles
+            .iter()
+            .map(absolute)
+            .collect::<Result<_, _>>()?;
+        request.detector = request.detector.profiles(profiles);
+        match request_daemon(&socket, &request)? {
+            Some(results) => return Ok(results),
+            None => debug!("No daemon listening on {:?}, detecting in-process", socket),
+        }
     }
-    debug!("Finished processing, printing results");
-    // safe unwrap because we checked for error above
-    if let Err(e) = serde_json::to_writer_pretty(stdout(), &result.unwrap()) {
-        error!("{}", e);
-        return ExitCode::FAILURE;
-    }
-    stdout().write_all("\n".as_bytes()).unwrap();
-
-    ExitCode::SUCCESS
+    Ok(json!(request.process()?))
 }
 
 #[cfg(test)]
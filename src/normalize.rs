@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::composition::is_emoji;
+use crate::DetectorConfig;
+
+/// The Unicode normalization form texts are converted to before detection.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizationForm {
+    /// Texts are detected as they are
+    #[default]
+    None,
+    /// Canonical composition, e.g. `e` followed by a combining accent becomes `é`
+    Nfc,
+    /// Compatibility composition, which additionally folds e.g. fullwidth letters and ligatures
+    Nfkc,
+}
+
+impl DetectorConfig {
+    /// Returns whether any step of the normalization is enabled.
+    pub(crate) fn normalizes(&self) -> bool {
+        self.normalize != NormalizationForm::None
+            || self.lowercase
+            || self.strip_urls
+            || self.strip_mentions
+            || self.strip_hashtags
+            || self.strip_emoji
+            || self.strip_numbers
+            || self.collapse_whitespace
+    }
+
+    /// Applies the enabled normalization steps to a text: the Unicode normalization form, the
+    /// removal of URLs, mentions, hashtags, numbers and emoji, lowercasing and the collapsing of
+    /// whitespace, in this order.
+    pub(crate) fn normalize_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = match self.normalize {
+            NormalizationForm::None => Cow::Borrowed(text),
+            NormalizationForm::Nfc => Cow::Owned(text.nfc().collect()),
+            NormalizationForm::Nfkc => Cow::Owned(text.nfkc().collect()),
+        };
+        if self.strip_urls || self.strip_mentions || self.strip_hashtags || self.strip_numbers {
+            text = Cow::Owned(self.strip_words(&text));
+        }
+        if self.strip_emoji {
+            text = Cow::Owned(text.graphemes(true).filter(|g| !is_emoji(g)).collect());
+        }
+        if self.lowercase {
+            text = Cow::Owned(text.to_lowercase());
+        }
+        if self.collapse_whitespace {
+            text = Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        text
+    }
+
+    /// Removes the words that are stripped, keeping the whitespace around them.
+    fn strip_words(&self, text: &str) -> String {
+        let mut stripped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let end = rest[start..]
+                .find(char::is_whitespace)
+                .map_or(rest.len(), |end| start + end);
+            let word = &rest[start..end];
+            stripped.push_str(&rest[..start]);
+            if !self.strips(word) {
+                stripped.push_str(word);
+            }
+            rest = &rest[end..];
+        }
+        stripped.push_str(rest);
+        stripped
+    }
+
+    fn strips(&self, word: &str) -> bool {
+        (self.strip_urls && is_url(word))
+            || (self.strip_mentions && word.len() > 1 && word.starts_with('@'))
+            || (self.strip_hashtags && word.len() > 1 && word.starts_with('#'))
+            || (self.strip_numbers && is_number(word))
+    }
+}
+
+fn is_url(word: &str) -> bool {
+    let word = word.to_lowercase();
+    word.starts_with("http://")
+        || word.starts_with("https://")
+        || word.starts_with("www.")
+        || word
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+}
+
+/// Returns whether a word is a number, e.g. `2024`, `3.5`, `+41` or `10%`.
+fn is_number(word: &str) -> bool {
+    word.chars().any(char::is_numeric) && !word.chars().any(char::is_alphabetic)
+}
@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::eval::{normalize_label, read_inputs};
+use crate::{DetectorConfig, LabeledFormat, LangInfo, LanguageDetector, WhatLangError};
+
+/// The number of most frequent trigrams kept per language unless another size is given.
+pub const DEFAULT_PROFILE_SIZE: usize = 300;
+
+/// The name of the backend that detects with the trained profiles alone.
+pub(crate) const PROFILES_BACKEND: &str = "profiles";
+
+/// How the labeled examples of the `train` subcommand are turned into profiles.
+#[derive(Debug, Clone)]
+pub struct TrainOptions {
+    /// The JSONL field or CSV column that contains the text
+    pub text_field: String,
+    /// The number of most frequent trigrams kept per language
+    pub size: usize,
+    /// The names reported for the languages by their codes. Defaults to the English name of
+    /// languages that whatlang knows and to the code otherwise
+    pub names: BTreeMap<String, String>,
+}
+
+impl Default for TrainOptions {
+    fn default() -> Self {
+        TrainOptions {
+            text_field: "text".to_string(),
+            size: DEFAULT_PROFILE_SIZE,
+            names: BTreeMap::new(),
+        }
+    }
+}
+
+/// The trigram profiles of any number of languages, written by the `train` subcommand and loaded
+/// with `--profiles`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LanguageProfiles {
+    profiles: Vec<LanguageProfile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LanguageProfile {
+    /// The code reported for the language, e.g. `gsw`
+    code: String,
+    /// The name reported for the language, e.g. `Swiss German`
+    name: String,
+    /// The script most examples are written in
+    script: String,
+    /// The number of examples the profile was trained on
+    examples: usize,
+    /// The relative frequencies of the most frequent trigrams
+    trigrams: BTreeMap<String, f64>,
+}
+
+impl LanguageProfiles {
+    /// Reads profiles written by [`LanguageProfiles::save`].
+    pub fn load(path: &Path) -> Result<LanguageProfiles, WhatLangError> {
+        let invalid = |message: String| WhatLangError::InvalidConfig {
+            message: format!("Can't load profiles {:?}: {}", path, message),
+        };
+        let file = File::open(path).map_err(|e| invalid(e.to_string()))?;
+        let profiles: LanguageProfiles =
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| invalid(e.to_string()))?;
+        for profile in &profiles.profiles {
+            validate_code(&profile.code).map_err(|e| invalid(e.to_string()))?;
+        }
+        Ok(profiles)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), WhatLangError> {
+        let mut out =
+            BufWriter::new(File::create(path).map_err(WhatLangError::write_failed(Some(path)))?);
+        serde_json::to_writer(&mut out, self)
+            .map_err(io::Error::from)
+            .and_then(|_| out.flush())
+            .map_err(WhatLangError::write_failed(Some(path)))
+    }
+
+    pub fn profiles(&self) -> &[LanguageProfile] {
+        &self.profiles
+    }
+}
+
+impl LanguageProfile {
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    pub fn examples(&self) -> usize {
+        self.examples
+    }
+
+    pub fn trigrams(&self) -> &BTreeMap<String, f64> {
+        &self.trigrams
+    }
+}
+
+/// Builds a trigram profile for every label of the labeled examples.
+///
+/// Labels are the codes reported for the languages, e.g. `gsw`. ISO 639-3 codes and English names
+/// of languages that whatlang knows are mapped to their codes, as by [`evaluate`](crate::evaluate).
+/// Since codes name the buckets of [`split_items`](crate::split_items), only ASCII letters, digits,
+/// `_` and `-` are allowed in them. Without inputs, the examples are read from stdin.
+pub fn train_profiles(
+    inputs: Vec<PathBuf>,
+    format: LabeledFormat,
+    label_field: &str,
+    options: &TrainOptions,
+) -> Result<LanguageProfiles, WhatLangError> {
+    // the trigram counts, the script counts and the number of examples of every label
+    let mut counts: BTreeMap<String, HashMap<String, usize>> = BTreeMap::new();
+    let mut scripts: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+    let mut examples: BTreeMap<String, usize> = BTreeMap::new();
+
+    let mut add = |example: Result<(String, String), WhatLangError>| match example {
+        Ok((text, label)) => {
+            let label = normalize_label(&label);
+            let trigrams = counts.entry(label.clone()).or_default();
+            for (trigram, count) in count_trigrams(&text) {
+                *trigrams.entry(trigram).or_default() += count;
+            }
+            if let Some(script) = whatlang::detect_script(&text) {
+                *scripts
+                    .entry(label.clone())
+                    .or_default()
+                    .entry(script.to_string())
+                    .or_default() += 1;
+            }
+            *examples.entry(label).or_default() += 1;
+        }
+        Err(e) => error!("Invalid example: {}. Skipping example", e),
+    };
+    read_inputs(&inputs, format, label_field, &options.text_field, &mut add)?;
+    for code in counts.keys() {
+        validate_code(code)?;
+    }
+
+    let profiles = counts
+        .into_iter()
+        .filter(|(_, trigrams)| !trigrams.is_empty())
+        .map(|(code, trigrams)| {
+            let mut trigrams: Vec<(String, usize)> = trigrams.into_iter().collect();
+            trigrams.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+            trigrams.truncate(options.size);
+            let total: usize = trigrams.iter().map(|(_, count)| count).sum();
+
+            // the first of the most frequent scripts
+            let script = scripts
+                .get(&code)
+                .and_then(|scripts| {
+                    scripts
+                        .iter()
+                        .rev()
+                        .max_by_key(|(_, count)| **count)
+                        .map(|(script, _)| script.clone())
+                })
+                .unwrap_or_default();
+            let name = options.names.get(&code).cloned().unwrap_or_else(|| {
+                whatlang::Lang::from_code(&code)
+                    .map_or_else(|| code.clone(), |lang| lang.eng_name().to_string())
+            });
+            LanguageProfile {
+                examples: examples[&code],
+                code,
+                name,
+                script,
+                trigrams: trigrams
+                    .into_iter()
+                    .map(|(trigram, count)| (trigram, count as f64 / total as f64))
+                    .collect(),
+            }
+        })
+        .collect();
+    Ok(LanguageProfiles { profiles })
+}
+
+/// Checks that a code consists of ASCII letters, digits, `_` and `-` only, so that it can't name a
+/// path outside the output directory of `split`.
+fn validate_code(code: &str) -> Result<(), WhatLangError> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if code.is_empty() || !code.chars().all(valid) {
+        return Err(WhatLangError::InvalidConfig {
+            message: format!(
+                "Invalid language code '{}', expected ASCII letters, digits, '_' and '-'",
+                code
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Counts the trigrams of the words of a text. Words are padded with a space on both sides, so
+/// that their beginnings and endings have trigrams of their own.
+fn count_trigrams(text: &str) -> BTreeMap<String, usize> {
+    let mut chars = vec![' '];
+    for c in text.chars() {
+        if c.is_alphabetic() {
+            chars.extend(c.to_lowercase());
+        } else if chars.last() != Some(&' ') {
+            chars.push(' ');
+        }
+    }
+    if chars.last() != Some(&' ') {
+        chars.push(' ');
+    }
+
+    let mut counts = BTreeMap::new();
+    for window in chars.windows(3) {
+        *counts.entry(window.iter().collect()).or_default() += 1;
+    }
+    counts
+}
+
+/// A backend that detects the languages of trained profiles, alone or alongside another backend.
+///
+/// The profiles of the script of a text compete by the cosine similarity of their trigram
+/// frequencies. Alone, as the `profiles` backend, the language of the best profile is detected.
+/// Alongside another backend, only texts that it detects as a language with a profile of its own,
+/// e.g. German, are detected again, and a custom language of the best profile, e.g. Swiss German,
+/// replaces the result of the other backend. Custom languages are thus only detected next to the
+/// related languages they were trained with.
+#[derive(Debug)]
+pub(crate) struct Profiles {
+    /// The profiles with the norms of their frequency vectors
+    profiles: Vec<(LanguageProfile, f64)>,
+    /// The codes of the custom languages that may be detected
+    allowed: Vec<String>,
+    /// `None` for the `profiles` backend and if the allowlist only contains custom languages
+    backend: Option<Arc<dyn LanguageDetector>>,
+}
+
+impl Profiles {
+    /// Builds the `profiles` backend, which detects every language of the profiles of a config.
+    pub(crate) fn standalone(config: &DetectorConfig) -> Result<Profiles, WhatLangError> {
+        if config.profiles.is_empty() {
+            return Err(WhatLangError::InvalidConfig {
+                message: format!("The backend '{}' needs profiles", PROFILES_BACKEND),
+            });
+        }
+        let profiles = load_profiles(config)?;
+        let allowed = profiles
+            .iter()
+            .filter(|(profile, _)| config.allows(&profile.code))
+            .map(|(profile, _)| profile.code.clone())
+            .collect();
+        Ok(Profiles {
+            profiles,
+            allowed,
+            backend: None,
+        })
+    }
+
+    /// Loads the profiles of a config and builds the other backend with `build`, passing it the
+    /// config without the codes of custom languages in its allowlist and denylist.
+    pub(crate) fn new(
+        config: &DetectorConfig,
+        build: impl FnOnce(&DetectorConfig) -> Result<Arc<dyn LanguageDetector>, WhatLangError>,
+    ) -> Result<Profiles, WhatLangError> {
+        let profiles = load_profiles(config)?;
+        let known = |code: &String| whatlang::Lang::from_code(code.to_lowercase()).is_some();
+        let mut inner = config.clone();
+        inner.allowlist.retain(known);
+        inner.denylist.retain(known);
+        let backend = if config.allowlist.is_empty() || !inner.allowlist.is_empty() {
+            Some(build(&inner)?)
+        } else {
+            None
+        };
+
+        let allowed = profiles
+            .iter()
+            .filter(|(profile, _)| !known(&profile.code) && config.allows(&profile.code))
+            .map(|(profile, _)| profile.code.clone())
+            .collect();
+        Ok(Profiles {
+            profiles,
+            allowed,
+            backend,
+        })
+    }
+}
+
+/// Loads the profiles of a config with the norms of their frequency vectors.
+fn load_profiles(config: &DetectorConfig) -> Result<Vec<(LanguageProfile, f64)>, WhatLangError> {
+    let mut profiles = vec![];
+    for path in &config.profiles {
+        profiles.extend(LanguageProfiles::load(path)?.profiles);
+    }
+    Ok(profiles
+        .into_iter()
+        .map(|profile| {
+            let norm = profile.trigrams.values().map(|f| f * f).sum::<f64>().sqrt();
+            (profile, norm)
+        })
+        .collect())
+}
+
+impl LanguageDetector for Profiles {
+    fn detect(&self, text: &str) -> Option<LangInfo> {
+        let info = self
+            .backend
+            .as_ref()
+            .and_then(|backend| backend.detect(text));
+        let contested = info.as_ref().is_none_or(|info| {
+            info.code()
+                .is_some_and(|code| self.profiles.iter().any(|(p, _)| p.code == code))
+        });
+        let script = whatlang::detect_script(text).map(|script| script.to_string());
+        let Some(script) = script.filter(|_| contested) else {
+            return info;
+        };
+
+        let trigrams = count_trigrams(text);
+        let norm = trigrams
+            .values()
+            .map(|&count| (count * count) as f64)
+            .sum::<f64>()
+            .sqrt();
+        let mut scores: Vec<(&LanguageProfile, f64)> = self
+            .profiles
+            .iter()
+            .filter(|(profile, _)| profile.script == script)
+            .map(|(profile, profile_norm)| {
+                let dot: f64 = trigrams
+                    .iter()
+                    .filter_map(|(trigram, &count)| {
+                        profile.trigrams.get(trigram).map(|f| f * count as f64)
+                    })
+                    .sum();
+                (profile, dot / (norm * profile_norm))
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        match scores.as_slice() {
+            [(best, score), rest @ ..] if self.allowed.contains(&best.code) => {
+                // the relative distance to the runner-up, as whatlang's trigram method computes it
+                let confidence = match rest.first() {
+                    Some((_, second)) => (score - second) / score,
+                    None => *score,
+                };
+                Some(LangInfo {
+                    code: Some(best.code.clone()),
+                    ..LangInfo::new(&best.name, script, confidence, confidence > 0.1)
+                })
+            }
+            _ => info,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::WhatLangError;
+
+/// The number of chunks the `spread` and `random` strategies read unless another number is given.
+pub const DEFAULT_CHUNKS: usize = 8;
+
+/// Which parts of a file are read if it is larger than the byte limit of its [`Sampling`].
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleStrategy {
+    /// The beginning of the file
+    #[default]
+    Head,
+    /// Chunks spread evenly over the file, from its beginning to its end
+    Spread,
+    /// Chunks at random offsets, which are the same for every run with the same seed
+    Random,
+}
+
+/// Reads only part of plain text files, e.g. of multi-GB logs, whose language a few KB reveal.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Sampling {
+    /// The number of bytes read of every file at most
+    max_bytes: usize,
+    strategy: SampleStrategy,
+    /// The number of chunks the byte limit is split into, except for the `head` strategy
+    chunks: usize,
+    /// The seed of the `random` strategy
+    seed: u64,
+}
+
+/// The sample of a file that was larger than the byte limit, reported with its results.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FileSample {
+    strategy: SampleStrategy,
+    chunks: usize,
+    /// The number of bytes that were read
+    bytes: usize,
+    /// The size of the file, unknown for pipes and other special files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file_bytes: Option<u64>,
+}
+
+impl Sampling {
+    /// Reads the first `max_bytes` of every file.
+    pub fn new(max_bytes: usize) -> Sampling {
+        Sampling {
+            max_bytes,
+            strategy: SampleStrategy::Head,
+            chunks: DEFAULT_CHUNKS,
+            seed: 0,
+        }
+    }
+
+    pub fn strategy(mut self, strategy: SampleStrategy) -> Sampling {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn chunks(mut self, chunks: usize) -> Sampling {
+        self.chunks = chunks;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Sampling {
+        self.seed = seed;
+        self
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Reads the sample of a file, or the whole file if it isn't larger than the byte limit.
+    /// Chunks are trimmed to whole UTF-8 characters and joined by newlines. Pipes and other
+    /// files that can't seek are sampled from their beginning.
+    pub(crate) fn read(&self, path: &Path) -> Result<(Vec<u8>, Option<FileSample>), WhatLangError> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() {
+            let mut buffer = Vec::new();
+            (&mut file)
+                .take(self.max_bytes as u64 + 1)
+                .read_to_end(&mut buffer)?;
+            if buffer.len() <= self.max_bytes {
+                return Ok((buffer, None));
+            }
+            buffer.truncate(self.max_bytes);
+            let buffer = trim_end(&buffer).to_vec();
+            let sample = FileSample {
+                strategy: SampleStrategy::Head,
+                chunks: 1,
+                bytes: buffer.len(),
+                file_bytes: None,
+            };
+            return Ok((buffer, Some(sample)));
+        }
+
+        let len = metadata.len();
+        if len <= self.max_bytes as u64 {
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            return Ok((buffer, None));
+        }
+        let chunks = match self.strategy {
+            SampleStrategy::Head => 1,
+            _ => self.chunks.clamp(1, self.max_bytes),
+        };
+        let size = (self.max_bytes / chunks) as u64;
+        let offsets: Vec<u64> = match self.strategy {
+            SampleStrategy::Head => vec![0],
+            SampleStrategy::Spread if chunks == 1 => vec![0],
+            SampleStrategy::Spread => (0..chunks as u64)
+                // in u128, so that the product can't overflow for any file size
+                .map(|i| (i as u128 * (len - size) as u128 / (chunks as u128 - 1)) as u64)
+                .collect(),
+            SampleStrategy::Random => random_slots(len / size, chunks, self.seed)
+                .into_iter()
+                .map(|slot| slot * size)
+                .collect(),
+        };
+
+        let mut buffer = Vec::with_capacity(self.max_bytes + chunks);
+        let mut bytes = 0;
+        let mut chunk = vec![0; size as usize];
+        for (i, offset) in offsets.into_iter().enumerate() {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut chunk)?;
+            let mut text = trim_end(&chunk);
+            if offset > 0 {
+                text = trim_start(text);
+            }
+            if i > 0 {
+                buffer.push(b'\n');
+            }
+            buffer.extend_from_slice(text);
+            bytes += text.len();
+        }
+        let sample = FileSample {
+            strategy: self.strategy,
+            chunks,
+            bytes,
+            file_bytes: Some(len),
+        };
+        Ok((buffer, Some(sample)))
+    }
+}
+
+impl FileSample {
+    pub fn strategy(&self) -> SampleStrategy {
+        self.strategy
+    }
+
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn file_bytes(&self) -> Option<u64> {
+        self.file_bytes
+    }
+}
+
+/// Picks `count` distinct slots out of `slots` in ascending order, with a SplitMix64 generator so
+/// that the same seed picks the same slots on every platform.
+fn random_slots(slots: u64, count: usize, seed: u64) -> BTreeSet<u64> {
+    let mut state = seed;
+    let mut picked = BTreeSet::new();
+    while picked.len() < count {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        picked.insert((z ^ (z >> 31)) % slots);
+    }
+    picked
+}
+
+/// Skips the continuation bytes of a character that began before the chunk.
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .take(3)
+        .take_while(|&&b| is_continuation(b))
+        .count();
+    &bytes[start..]
+}
+
+/// Cuts off a character whose bytes continue after the chunk.
+fn trim_end(bytes: &[u8]) -> &[u8] {
+    let tail = bytes.len().saturating_sub(4);
+    let Some(start) = (tail..bytes.len())
+        .rev()
+        .find(|&i| !is_continuation(bytes[i]))
+    else {
+        return bytes;
+    };
+    let len = match bytes[start] {
+        b if b < 0x80 => 1,
+        b if b >= 0xF0 => 4,
+        b if b >= 0xE0 => 3,
+        _ => 2,
+    };
+    if start + len > bytes.len() {
+        &bytes[..start]
+    } else {
+        bytes
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
@@ -0,0 +1,360 @@
+use std::cmp::Reverse;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{LangDetector, LangInfo, TooShortInfo, WhatLangResult};
+
+/// How a text is split into segments before their languages are detected.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentMode {
+    /// Split at Unicode sentence boundaries
+    Sentence,
+    /// Split at blank lines
+    Paragraph,
+    /// Detect windows of `size` characters every `step` characters, each window deciding the
+    /// language of its first `step` characters
+    Window { size: usize, step: usize },
+}
+
+/// A span of text in a single language. `start` and `end` are byte offsets into the input.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhatLangSpan {
+    start: usize,
+    end: usize,
+    result: WhatLangResult,
+}
+
+/// The share of a language in a text, weighted by character count. A `language` of `None`
+/// stands for text whose language couldn't be detected.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LanguageShare {
+    language: Option<String>,
+    characters: usize,
+    ratio: f64,
+}
+
+impl WhatLangSpan {
+    /// Returns the byte offset of the start of the span.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the byte offset of the end of the span.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn result(&self) -> &WhatLangResult {
+        &self.result
+    }
+
+    /// Moves the span by `offset` bytes, e.g. from a paragraph into the stream it was read from.
+    pub(crate) fn shift(mut self, offset: usize) -> WhatLangSpan {
+        self.start += offset;
+        self.end += offset;
+        self
+    }
+
+    /// Returns the number of characters the span covers in `text`.
+    pub(crate) fn characters(&self, text: &str) -> usize {
+        text[self.start..self.end].chars().count()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhatLangSegmentation {
+    spans: Vec<WhatLangSpan>,
+    distribution: Vec<LanguageShare>,
+}
+
+impl WhatLangSegmentation {
+    pub fn spans(&self) -> &[WhatLangSpan] {
+        &self.spans
+    }
+
+    pub fn distribution(&self) -> &[LanguageShare] {
+        &self.distribution
+    }
+}
+
+impl LanguageShare {
+    /// Returns the English name of the language, or `None` for undetected text.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn characters(&self) -> usize {
+        self.characters
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+}
+
+/// Splits a text into segments with the default detector, see [`LangDetector::segment`].
+pub fn segment(text: &str, mode: SegmentMode) -> WhatLangSegmentation {
+    LangDetector::default().segment(text, mode)
+}
+
+/// Detects every sentence of a text with the default detector, see
+/// [`LangDetector::detect_sentences`].
+pub fn detect_sentences(text: &str) -> Vec<WhatLangSpan> {
+    LangDetector::default().detect_sentences(text)
+}
+
+impl LangDetector {
+    /// Splits a text into segments, detects the language of each segment and merges adjacent
+    /// segments of the same language into spans.
+    pub fn segment(&self, text: &str, mode: SegmentMode) -> WhatLangSegmentation {
+        let mut spans: Vec<WhatLangSpan> = vec![];
+        let mut weights: Vec<usize> = vec![];
+        for (span, detected) in segments(text, mode) {
+            let result = self.detect(&text[detected]);
+            let characters = text[span.clone()].chars().count();
+            match spans.last_mut() {
+                Some(last) if same_language(&last.result, &result) => {
+                    let weight = weights.last_mut().unwrap();
+                    last.result = merge(&last.result, *weight, &result, characters);
+                    last.end = span.end;
+                    *weight += characters;
+                }
+                _ => {
+                    spans.push(WhatLangSpan {
+                        start: span.start,
+                        end: span.end,
+                        result,
+                    });
+                    weights.push(characters);
+                }
+            }
+        }
+
+        let distribution = distribution(spans.iter().zip(weights).map(|(s, w)| (&s.result, w)));
+        WhatLangSegmentation {
+            spans,
+            distribution,
+        }
+    }
+
+    /// Splits a text at Unicode sentence boundaries and detects the language of every sentence on
+    /// its own, without merging sentences of the same language.
+    pub fn detect_sentences(&self, text: &str) -> Vec<WhatLangSpan> {
+        self.detect_each(text, SegmentMode::Sentence)
+    }
+
+    /// Detects the language of every segment of a text on its own.
+    pub(crate) fn detect_each(&self, text: &str, mode: SegmentMode) -> Vec<WhatLangSpan> {
+        segments(text, mode)
+            .into_iter()
+            .map(|(span, detected)| WhatLangSpan {
+                start: span.start,
+                end: span.end,
+                result: self.detect(&text[detected]),
+            })
+            .collect()
+    }
+}
+
+/// Combines the language distributions of several segmented texts into a single distribution.
+pub fn merge_distributions(segmentations: &[WhatLangSegmentation]) -> Vec<LanguageShare> {
+    shares(
+        segmentations
+            .iter()
+            .flat_map(|s| &s.distribution)
+            .map(|share| (share.language.clone(), share.characters)),
+    )
+}
+
+/// Computes the share of every language among the given results, weighted by their character
+/// counts, ordered from the largest share to the smallest.
+fn distribution<'a>(
+    results: impl Iterator<Item = (&'a WhatLangResult, usize)>,
+) -> Vec<LanguageShare> {
+    shares(results.map(|(result, characters)| match result {
+        WhatLangResult::Ok(info) => (Some(info.language.clone()), characters),
+        WhatLangResult::Error(_) | WhatLangResult::TooShort(_) => (None, characters),
+    }))
+}
+
+fn shares(entries: impl Iterator<Item = (Option<String>, usize)>) -> Vec<LanguageShare> {
+    let mut shares: Vec<LanguageShare> = vec![];
+    for (language, characters) in entries {
+        match shares.iter_mut().find(|s| s.language == language) {
+            Some(share) => share.characters += characters,
+            None => shares.push(LanguageShare {
+                language,
+                characters,
+                ratio: 0.0,
+            }),
+        }
+    }
+
+    let total: usize = shares.iter().map(|s| s.characters).sum();
+    for share in shares.iter_mut() {
+        share.ratio = share.characters as f64 / total as f64;
+    }
+    shares.sort_by_key(|s| Reverse(s.characters));
+    shares
+}
+
+/// Returns the byte ranges of all segments as pairs of the range the segment covers and the
+/// range whose text is used to detect its language.
+fn segments(text: &str, mode: SegmentMode) -> Vec<(Range<usize>, Range<usize>)> {
+    let ranges = match mode {
+        SegmentMode::Sentence => split_sentences(text),
+        SegmentMode::Paragraph => split_paragraphs(text),
+        SegmentMode::Window { size, step } => return windows(text, size, step),
+    };
+    ranges
+        .into_iter()
+        .map(|range| trim(text, range))
+        .filter(|range| !range.is_empty())
+        .map(|range| (range.clone(), range))
+        .collect()
+}
+
+/// Splits a text at Unicode sentence boundaries (UAX #29).
+fn split_sentences(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    for (offset, sentence) in text.split_sentence_bound_indices() {
+        // UAX #29 attaches opening brackets that follow a CJK full stop to the previous sentence,
+        // so they are moved to the start of the next one
+        let mut chars = sentence.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let opening = chars.peek().is_some_and(|(_, next)| is_cjk_opening(*next));
+            if is_cjk_terminal(c) && opening {
+                let end = offset + i + c.len_utf8();
+                ranges.push(start..end);
+                start = end;
+            }
+        }
+        let end = offset + sentence.len();
+        if !text[start..end].chars().all(is_cjk_opening) {
+            ranges.push(start..end);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        ranges.push(start..text.len());
+    }
+    ranges
+}
+
+fn is_cjk_terminal(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '｡' | '．')
+}
+
+fn is_cjk_opening(c: char) -> bool {
+    matches!(c, '「' | '『' | '（' | '【' | '〈' | '《' | '〔' | '｢')
+}
+
+fn split_paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut offset = 0;
+    let mut previous_blank = false;
+    for line in text.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        if blank && !previous_blank {
+            ranges.push(start..offset);
+            start = offset;
+        }
+        offset += line.len();
+        previous_blank = blank;
+    }
+    ranges.push(start..offset);
+    ranges
+}
+
+fn windows(text: &str, size: usize, step: usize) -> Vec<(Range<usize>, Range<usize>)> {
+    let size = size.max(1);
+    let step = step.clamp(1, size);
+    let boundaries: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let chars = boundaries.len() - 1;
+
+    let mut segments = vec![];
+    let mut start = 0;
+    while start < chars {
+        let window_end = (start + size).min(chars);
+        // the last window covers all remaining characters
+        let span_end = if window_end == chars {
+            chars
+        } else {
+            start + step
+        };
+        segments.push((
+            boundaries[start]..boundaries[span_end],
+            boundaries[start]..boundaries[window_end],
+        ));
+        start = span_end;
+    }
+    segments
+}
+
+fn trim(text: &str, range: Range<usize>) -> Range<usize> {
+    let segment = &text[range.clone()];
+    let start = range.start + (segment.len() - segment.trim_start().len());
+    let end = range.end - (segment.len() - segment.trim_end().len());
+    start..end.max(start)
+}
+
+fn same_language(a: &WhatLangResult, b: &WhatLangResult) -> bool {
+    match (a, b) {
+        (WhatLangResult::Ok(a), WhatLangResult::Ok(b)) => a.language == b.language,
+        (WhatLangResult::Error(_), WhatLangResult::Error(_)) => true,
+        (WhatLangResult::TooShort(_), WhatLangResult::TooShort(_)) => true,
+        _ => false,
+    }
+}
+
+/// Merges two results of the same language, weighting the confidence by character count.
+fn merge(
+    a: &WhatLangResult,
+    a_weight: usize,
+    b: &WhatLangResult,
+    b_weight: usize,
+) -> WhatLangResult {
+    match (a, b) {
+        (WhatLangResult::Ok(a), WhatLangResult::Ok(b)) => {
+            let total = (a_weight + b_weight).max(1) as f64;
+            WhatLangResult::Ok(LangInfo {
+                language: a.language.clone(),
+                script: a.script.clone(),
+                confidence: (a.confidence * a_weight as f64 + b.confidence * b_weight as f64)
+                    / total,
+                is_reliable: a.is_reliable && b.is_reliable,
+                agreement: None,
+                votes: None,
+                code: a.code.clone(),
+                composition: match (&a.composition, &b.composition) {
+                    (Some(a), Some(b)) => Some(a.merge(b)),
+                    _ => None,
+                },
+                normalized_characters: a
+                    .normalized_characters
+                    .zip(b.normalized_characters)
+                    .map(|(a, b)| a + b),
+            })
+        }
+        (WhatLangResult::TooShort(a), WhatLangResult::TooShort(b)) => {
+            WhatLangResult::TooShort(TooShortInfo {
+                characters: a.characters + b.characters,
+                words: a.words + b.words,
+                composition: match (&a.composition, &b.composition) {
+                    (Some(a), Some(b)) => Some(a.merge(b)),
+                    _ => None,
+                },
+            })
+        }
+        _ => a.clone(),
+    }
+}
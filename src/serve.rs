@@ -0,0 +1,157 @@
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, error, info};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{LangDetector, WhatLangError};
+
+/// How often the workers check whether the server is shutting down.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options of the HTTP server of the `serve` subcommand.
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// The address to listen on, e.g. `127.0.0.1:8080`
+    pub address: String,
+    /// Requests with larger bodies are rejected with `413 Payload Too Large`
+    pub max_body_bytes: usize,
+    /// The number of requests that are handled concurrently
+    pub workers: usize,
+    pub detector: LangDetector,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        ServeOptions {
+            address: "127.0.0.1:8080".to_string(),
+            max_body_bytes: 1024 * 1024,
+            workers: thread::available_parallelism().map_or(4, |n| n.get()),
+            detector: LangDetector::default(),
+        }
+    }
+}
+
+/// Serves the JSON API over HTTP until `shutdown` is set:
+///
+/// - `POST /detect` takes a JSON string and returns its [`WhatLangResult`](crate::WhatLangResult)
+/// - `POST /detect/batch` takes a JSON array of strings and returns an array of results
+/// - `GET /health` returns `200` while the process is running
+/// - `GET /ready` returns `200` while requests are accepted and `503` once the server shuts down
+///
+/// The path of a request is matched without its query string. On shutdown, `/ready` returns `503`
+/// and the server keeps answering requests until none arrived for a moment, so that the requests
+/// queued or sent before load balancers noticed aren't dropped.
+pub fn serve(options: &ServeOptions, shutdown: Arc<AtomicBool>) -> Result<(), WhatLangError> {
+    let server = Server::http(&options.address).map_err(|e| WhatLangError::Listen {
+        address: options.address.clone(),
+        source: io::Error::other(e),
+    })?;
+    let server = Arc::new(server);
+    info!("Listening on {}", server.server_addr());
+
+    let workers: Vec<_> = (0..options.workers.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let shutdown = Arc::clone(&shutdown);
+            let options = options.clone();
+            thread::spawn(move || {
+                loop {
+                    match server.recv_timeout(POLL_INTERVAL) {
+                        Ok(Some(request)) => handle(request, &options, &shutdown),
+                        // the queue is drained
+                        Ok(None) if shutdown.load(Ordering::SeqCst) => break,
+                        Ok(None) => {}
+                        Err(e) => error!("Failed to receive request: {}", e),
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        if worker.join().is_err() {
+            error!("A worker panicked");
+        }
+    }
+    info!("Shut down");
+    Ok(())
+}
+
+fn handle(mut request: Request, options: &ServeOptions, shutdown: &AtomicBool) {
+    debug!("{} {}", request.method(), request.url());
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let (status, body) = match (request.method(), path.as_str()) {
+        (Method::Get, "/health") => (200, json!({"status": "ok"})),
+        (Method::Get, "/ready") if shutdown.load(Ordering::SeqCst) => {
+            (503, json!({"status": "shutting down"}))
+        }
+        (Method::Get, "/ready") => (200, json!({"status": "ready"})),
+        (Method::Post, "/detect") => match read_body(&mut request, options.max_body_bytes) {
+            Ok(body) => detect_single(&options.detector, &body),
+            Err(response) => response,
+        },
+        (Method::Post, "/detect/batch") => match read_body(&mut request, options.max_body_bytes) {
+            Ok(body) => detect_batch(&options.detector, &body),
+            Err(response) => response,
+        },
+        (_, "/health" | "/ready" | "/detect" | "/detect/batch") => {
+            (405, json!({"error": "Method not allowed"}))
+        }
+        _ => (404, json!({"error": "Not found"})),
+    };
+
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    if let Err(e) = request.respond(response) {
+        error!("Failed to send response: {}", e);
+    }
+}
+
+/// Reads the body of a request as UTF-8, or returns the error response if it is too large or
+/// invalid.
+fn read_body(request: &mut Request, max_body_bytes: usize) -> Result<String, (u16, Value)> {
+    let too_large = || (413, json!({"error": "Payload too large"}));
+    if request
+        .body_length()
+        .is_some_and(|length| length > max_body_bytes)
+    {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    let limit = max_body_bytes as u64 + 1;
+    if let Err(e) = request.as_reader().take(limit).read_to_end(&mut body) {
+        return Err((400, json!({"error": e.to_string()})));
+    }
+    if body.len() > max_body_bytes {
+        return Err(too_large());
+    }
+    String::from_utf8(body).map_err(|e| (400, json!({"error": e.to_string()})))
+}
+
+fn detect_single(detector: &LangDetector, body: &str) -> (u16, Value) {
+    match serde_json::from_str::<String>(body) {
+        Ok(text) => (200, json!(detector.detect(&text))),
+        Err(e) => (
+            400,
+            json!({"error": format!("Expected a JSON string: {}", e)}),
+        ),
+    }
+}
+
+fn detect_batch(detector: &LangDetector, body: &str) -> (u16, Value) {
+    match detector.detect_json(body) {
+        Ok(results) => (200, json!(results)),
+        Err(e) => (400, json!({"error": e.to_string()})),
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::items::{for_each_item, ItemFormat, ItemOptions};
+use crate::WhatLangError;
+
+/// The bucket of items whose language couldn't be detected or whose result the detector rejects,
+/// e.g. unreliable results with `reliable_only`, named after the ISO 639 code for undetermined
+/// languages.
+pub const UNDETERMINED: &str = "und";
+
+/// The number of items written into every bucket.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct WhatLangSplitReport {
+    buckets: BTreeMap<String, usize>,
+    /// The number of items that were skipped due to errors
+    skipped: usize,
+}
+
+/// Routes every item into a bucket named after the ISO 639-3 code of its language.
+///
+/// Lines and JSONL records are written into `<out_dir>/<code>.jsonl`, plain lines as objects with
+/// the text in the `field` of `options`. Files are copied (or symlinked) into `<out_dir>/<code>/`.
+/// Items whose language couldn't be detected or whose result the detector rejects go into the
+/// [`UNDETERMINED`] bucket. A file whose name is already taken in its bucket, e.g. by a file of
+/// the same name in another input directory, isn't overwritten but logged and skipped.
+pub fn split_items(
+    inputs: Vec<PathBuf>,
+    options: &ItemOptions,
+    out_dir: &Path,
+    symlink: bool,
+) -> Result<WhatLangSplitReport, WhatLangError> {
+    fs::create_dir_all(out_dir).map_err(WhatLangError::write_failed(Some(out_dir)))?;
+    let mut report = WhatLangSplitReport::default();
+    let mut writers: BTreeMap<String, BufWriter<File>> = BTreeMap::new();
+
+    for_each_item(&inputs, options.format, |item| {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                error!("{}. Skipping item", e);
+                report.skipped += 1;
+                return Ok(());
+            }
+        };
+        let bucket = match options.detect(item) {
+            Ok(info) => info.map_or(UNDETERMINED.to_string(), |info| {
+                info.code().unwrap_or(UNDETERMINED).to_string()
+            }),
+            Err(e) => {
+                error!("Invalid item '{}': {}. Skipping item", item, e);
+                report.skipped += 1;
+                return Ok(());
+            }
+        };
+        check_bucket(&bucket)?;
+
+        let routed = match options.format {
+            ItemFormat::Files => route_file(Path::new(item), &out_dir.join(&bucket), symlink),
+            ItemFormat::Lines | ItemFormat::Jsonl => {
+                let path = bucket_file(out_dir, &bucket);
+                let writer = match writers.entry(bucket.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let file = File::create(&path)
+                            .map_err(WhatLangError::write_failed(Some(&path)))?;
+                        entry.insert(BufWriter::new(file))
+                    }
+                };
+                write_record(writer, item, options)
+                    .map_err(WhatLangError::write_failed(Some(&path)))
+            }
+        };
+        if let Err(e) = routed {
+            error!("Failed to route item '{}': {}. Skipping item", item, e);
+            report.skipped += 1;
+            return Ok(());
+        }
+        *report.buckets.entry(bucket).or_default() += 1;
+        Ok(())
+    })?;
+
+    for (bucket, writer) in &mut writers {
+        writer
+            .flush()
+            .map_err(WhatLangError::write_failed(Some(&bucket_file(
+                out_dir, bucket,
+            ))))?;
+    }
+    Ok(report)
+}
+
+/// Checks that a bucket names a single file or directory in the output directory, since the codes
+/// of profiles and backends could otherwise route items anywhere.
+fn check_bucket(bucket: &str) -> Result<(), WhatLangError> {
+    let mut components = Path::new(bucket).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) if name == bucket => Ok(()),
+        _ => Err(WhatLangError::InvalidConfig {
+            message: format!("The language code '{}' can't name a bucket", bucket),
+        }),
+    }
+}
+
+fn bucket_file(out_dir: &Path, bucket: &str) -> PathBuf {
+    out_dir.join(format!("{}.jsonl", bucket))
+}
+
+fn write_record(writer: &mut impl Write, item: &str, options: &ItemOptions) -> io::Result<()> {
+    if options.format == ItemFormat::Lines {
+        let mut record = Map::new();
+        record.insert(options.field.clone(), Value::String(item.to_string()));
+        serde_json::to_writer(&mut *writer, &record)?;
+    } else {
+        writer.write_all(item.as_bytes())?;
+    }
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn route_file(file: &Path, dir: &Path, symlink: bool) -> Result<(), WhatLangError> {
+    let name = file.file_name().ok_or_else(|| WhatLangError::Io {
+        path: Some(file.to_path_buf()),
+        source: io::Error::new(ErrorKind::InvalidInput, "Input is not a file"),
+    })?;
+    let target = dir.join(name);
+    fs::create_dir_all(dir).map_err(WhatLangError::write_failed(Some(dir)))?;
+    if symlink {
+        let source = file
+            .canonicalize()
+            .map_err(|e| WhatLangError::from(e).in_file(file))?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(source, &target)
+            .map_err(WhatLangError::write_failed(Some(&target)))?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(source, &target)
+            .map_err(WhatLangError::write_failed(Some(&target)))?;
+    } else {
+        // claims the name first, so that a file of the same name isn't overwritten
+        File::create_new(&target)
+            .and_then(|_| fs::copy(file, &target))
+            .map_err(WhatLangError::write_failed(Some(&target)))?;
+    }
+    Ok(())
+}
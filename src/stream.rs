@@ -0,0 +1,199 @@
+use std::io::BufRead;
+
+use serde_json::Value;
+
+use crate::{LangDetector, SegmentMode, WhatLangError, WhatLangResult, WhatLangSpan};
+
+impl LangDetector {
+    /// Lazily detects the language of every text of an iterator.
+    pub fn detect_iter<'a, I>(&'a self, texts: I) -> impl Iterator<Item = WhatLangResult> + 'a
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+        I::Item: AsRef<str>,
+    {
+        texts.into_iter().map(|text| self.detect(text.as_ref()))
+    }
+
+    /// Lazily detects the language of every line of a reader, so that inputs of any size are
+    /// processed in constant memory.
+    ///
+    /// A line that isn't valid UTF-8 yields an `InvalidUtf8` error with the byte offset into the
+    /// stream, and reading continues with the next line. Reading stops after an I/O error.
+    pub fn detect_lines<'a>(
+        &'a self,
+        reader: impl BufRead + 'a,
+    ) -> impl Iterator<Item = Result<WhatLangResult, WhatLangError>> + 'a {
+        Lines::new(reader).map(|line| Ok(self.detect(trim_newline(&line?.1))))
+    }
+
+    /// Lazily detects the language of every JSONL record of a reader. A record is either a JSON
+    /// string or a JSON object with the text in `field`.
+    ///
+    /// An invalid record yields an `InvalidJson` error with its line number, and reading continues
+    /// with the next record. Reading stops after an I/O error.
+    pub fn detect_jsonl<'a>(
+        &'a self,
+        reader: impl BufRead + 'a,
+        field: &'a str,
+    ) -> impl Iterator<Item = Result<WhatLangResult, WhatLangError>> + 'a {
+        Lines::new(reader).enumerate().map(move |(i, line)| {
+            let text = jsonl_text(trim_newline(&line?.1), field).map_err(|e| match e {
+                WhatLangError::InvalidJson {
+                    path,
+                    column,
+                    message,
+                    ..
+                } => WhatLangError::InvalidJson {
+                    path,
+                    line: i + 1,
+                    column,
+                    message,
+                },
+                e => e,
+            })?;
+            Ok(self.detect(&text))
+        })
+    }
+
+    /// Lazily detects the language of every segment of a reader, without merging segments of the
+    /// same language. The reader is consumed paragraph by paragraph, so segments never cross a
+    /// blank line. `start` and `end` of the spans are byte offsets into the stream.
+    ///
+    /// Lines that aren't valid UTF-8 and I/O errors are handled as by [`LangDetector::detect_lines`].
+    pub fn detect_segments<'a>(
+        &'a self,
+        reader: impl BufRead + 'a,
+        mode: SegmentMode,
+    ) -> impl Iterator<Item = Result<WhatLangSpan, WhatLangError>> + 'a {
+        Paragraphs::new(reader).flat_map(move |paragraph| match paragraph {
+            Ok((start, text)) => self
+                .detect_each(&text, mode)
+                .into_iter()
+                .map(|span| Ok(span.shift(start)))
+                .collect(),
+            Err(e) => vec![Err(e)],
+        })
+    }
+}
+
+/// Returns the text of a JSONL record, which is either a JSON string or a JSON object with the
+/// text in `field`.
+pub(crate) fn jsonl_text(line: &str, field: &str) -> Result<String, WhatLangError> {
+    let invalid = |message: String| WhatLangError::InvalidJson {
+        path: None,
+        line: 1,
+        column: 1,
+        message,
+    };
+    match serde_json::from_str(line)? {
+        Value::String(text) => Ok(text),
+        Value::Object(mut object) => match object.remove(field) {
+            Some(Value::String(text)) => Ok(text),
+            _ => Err(invalid(format!("Missing string field '{}'", field))),
+        },
+        _ => Err(invalid("Expected a JSON string or object".to_string())),
+    }
+}
+
+pub(crate) fn trim_newline(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map_or(line, |line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// The lines of a reader with their line terminators and the byte offsets of their starts.
+pub(crate) struct Lines<R> {
+    reader: R,
+    offset: usize,
+    failed: bool,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub(crate) fn new(reader: R) -> Lines<R> {
+        Lines {
+            reader,
+            offset: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<(usize, String), WhatLangError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let mut buffer = vec![];
+        match self.reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => None,
+            Ok(read) => {
+                let start = self.offset;
+                self.offset += read;
+                Some(
+                    String::from_utf8(buffer)
+                        .map(|line| (start, line))
+                        .map_err(|e| WhatLangError::InvalidUtf8 {
+                            path: None,
+                            offset: start + e.utf8_error().valid_up_to(),
+                        }),
+                )
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// The paragraphs of a reader, separated by blank lines, with the byte offsets of their starts.
+/// A line that isn't valid UTF-8 ends the paragraph before it.
+struct Paragraphs<R> {
+    lines: Lines<R>,
+    pending: Option<WhatLangError>,
+}
+
+impl<R: BufRead> Paragraphs<R> {
+    fn new(reader: R) -> Paragraphs<R> {
+        Paragraphs {
+            lines: Lines::new(reader),
+            pending: None,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Paragraphs<R> {
+    type Item = Result<(usize, String), WhatLangError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        let mut paragraph: Option<(usize, String)> = None;
+        loop {
+            match self.lines.next() {
+                None => return paragraph.map(Ok),
+                Some(Err(e)) => match paragraph {
+                    Some(paragraph) => {
+                        self.pending = Some(e);
+                        return Some(Ok(paragraph));
+                    }
+                    None => return Some(Err(e)),
+                },
+                Some(Ok((_, line))) if line.trim().is_empty() => {
+                    if paragraph.is_some() {
+                        return paragraph.map(Ok);
+                    }
+                }
+                Some(Ok((start, line))) => {
+                    paragraph
+                        .get_or_insert_with(|| (start, String::new()))
+                        .1
+                        .push_str(&line);
+                }
+            }
+        }
+    }
+}
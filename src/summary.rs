@@ -0,0 +1,202 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Report, WhatLangResult};
+
+/// Aggregate statistics over all results of a run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhatLangSummary {
+    /// The number of detected texts, sentences or spans
+    items: usize,
+    characters: usize,
+    /// The number of items whose language couldn't be detected
+    errors: usize,
+    /// The number of items that were too short to detect their language
+    too_short: usize,
+    /// The number of files or messages that were skipped due to errors
+    skipped: usize,
+    /// The share of reliable results among all items whose language was detected
+    reliability_ratio: f64,
+    languages: Vec<LanguageSummary>,
+    scripts: Vec<ScriptSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LanguageSummary {
+    language: String,
+    count: usize,
+    characters: usize,
+    mean_confidence: f64,
+    median_confidence: f64,
+    reliability_ratio: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptSummary {
+    script: String,
+    count: usize,
+    characters: usize,
+}
+
+/// Computes the summary of results paired with the character count of their text.
+pub fn summarize<'a>(
+    results: impl IntoIterator<Item = (&'a WhatLangResult, usize)>,
+) -> WhatLangSummary {
+    let mut tally = Tally::new(true);
+    for (result, characters) in results {
+        tally.add(result, characters);
+    }
+    tally.summarize()
+}
+
+/// Counts results while they are detected so that a summary can be computed at the end. Of
+/// every result, only what the summary needs is kept.
+#[derive(Debug)]
+pub(crate) struct Tally {
+    /// Whether results are counted at all, since most runs don't report a summary
+    active: bool,
+    items: usize,
+    characters: usize,
+    errors: usize,
+    too_short: usize,
+    skipped: usize,
+    reliable: usize,
+    languages: Vec<LanguageTally>,
+    scripts: Vec<ScriptSummary>,
+}
+
+impl Tally {
+    pub(crate) fn new(active: bool) -> Tally {
+        Tally {
+            active,
+            items: 0,
+            characters: 0,
+            errors: 0,
+            too_short: 0,
+            skipped: 0,
+            reliable: 0,
+            languages: vec![],
+            scripts: vec![],
+        }
+    }
+
+    /// Returns a tally that only counts if the report contains a summary.
+    pub(crate) fn for_report(report: Report) -> Tally {
+        Tally::new(report != Report::Results)
+    }
+
+    pub(crate) fn add(&mut self, result: &WhatLangResult, characters: usize) {
+        if !self.active {
+            return;
+        }
+        self.items += 1;
+        self.characters += characters;
+        let info = match result {
+            WhatLangResult::Ok(info) => info,
+            WhatLangResult::Error(_) => {
+                self.errors += 1;
+                return;
+            }
+            WhatLangResult::TooShort(_) => {
+                self.too_short += 1;
+                return;
+            }
+        };
+        let reliable = usize::from(info.is_reliable);
+        self.reliable += reliable;
+        match self
+            .languages
+            .iter_mut()
+            .find(|l| l.language == info.language)
+        {
+            Some(language) => {
+                language.confidences.push(info.confidence);
+                language.characters += characters;
+                language.reliable += reliable;
+            }
+            None => self.languages.push(LanguageTally {
+                language: info.language.clone(),
+                confidences: vec![info.confidence],
+                characters,
+                reliable,
+            }),
+        }
+        match self.scripts.iter_mut().find(|s| s.script == info.script) {
+            Some(script) => {
+                script.count += 1;
+                script.characters += characters;
+            }
+            None => self.scripts.push(ScriptSummary {
+                script: info.script.clone(),
+                count: 1,
+                characters,
+            }),
+        }
+    }
+
+    pub(crate) fn skip(&mut self, count: usize) {
+        self.skipped += count;
+    }
+
+    pub(crate) fn summarize(&self) -> WhatLangSummary {
+        let mut languages: Vec<LanguageSummary> = self
+            .languages
+            .iter()
+            .map(|tally| {
+                let mut confidences = tally.confidences.clone();
+                confidences.sort_by(f64::total_cmp);
+                let count = confidences.len();
+                LanguageSummary {
+                    language: tally.language.clone(),
+                    count,
+                    characters: tally.characters,
+                    mean_confidence: confidences.iter().sum::<f64>() / count as f64,
+                    median_confidence: median(&confidences),
+                    reliability_ratio: ratio(tally.reliable, count),
+                }
+            })
+            .collect();
+        languages.sort_by_key(|l| Reverse(l.count));
+        let mut scripts = self.scripts.clone();
+        scripts.sort_by_key(|s| Reverse(s.count));
+
+        WhatLangSummary {
+            items: self.items,
+            characters: self.characters,
+            errors: self.errors,
+            too_short: self.too_short,
+            skipped: self.skipped,
+            reliability_ratio: ratio(self.reliable, self.items - self.errors - self.too_short),
+            languages,
+            scripts,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LanguageTally {
+    language: String,
+    confidences: Vec<f64>,
+    characters: usize,
+    reliable: usize,
+}
+
+/// Returns the median of sorted, non-empty values.
+fn median(sorted: &[f64]) -> f64 {
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Returns `count / total`, or 0 if `total` is 0.
+pub(crate) fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
@@ -0,0 +1,213 @@
+use std::io::{BufRead, Write};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{DetectorConfig, LangDetector, ProcessRequest, WhatLangError};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PROCESS_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Requests without an id are notifications, which aren't answered. A `null` id is answered.
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Deserializes a field that is present, including `null`, as `Some`, so that only a missing
+/// field becomes `None`.
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    /// The code of the [`WhatLangError`] a `process` request failed with, e.g. `{"code": "io"}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// Answers newline-delimited JSON-RPC 2.0 requests from `input` on `output`, one response per
+/// line, until `input` ends. Invalid requests, including lines that aren't valid UTF-8, are answered
+/// with an error and don't end the worker.
+///
+/// Methods:
+/// - `detect` with `{"text": ...}` or `[text]` returns a [`WhatLangResult`](crate::WhatLangResult)
+/// - `detect_batch` with `{"texts": [...]}` or `[[...]]` returns an array of results
+/// - `detect_script` with `{"text": ...}` or `[text]` returns the name of the script or `null`
+/// - `process` with a [`ProcessRequest`] returns the output of a whole detection run as printed by
+///   the CLI. If the run fails, the `data` of the error contains the code of the [`WhatLangError`]
+/// - `options` with optional fields of a [`DetectorConfig`], e.g. `{"allowlist": ["deu", "eng"]}`,
+///   changes the detector of the following `detect` and `detect_batch` requests and returns its
+///   config
+///
+/// The session starts with `detector`.
+pub fn run_worker(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    detector: LangDetector,
+) -> Result<(), WhatLangError> {
+    let mut session = detector;
+    let mut buffer = Vec::new();
+    while input.read_until(b'\n', &mut buffer)? > 0 {
+        let response = match String::from_utf8(std::mem::take(&mut buffer)) {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => answer(&line, &mut session),
+            Err(e) => Some(error_response(
+                Value::Null,
+                PARSE_ERROR,
+                format!(
+                    "Invalid UTF-8 in request at byte offset {}",
+                    e.utf8_error().valid_up_to()
+                ),
+            )),
+        };
+        if let Some(response) = response {
+            let mut line = serde_json::to_vec(&response)?;
+            line.push(b'\n');
+            output
+                .write_all(&line)
+                .and_then(|_| output.flush())
+                .map_err(WhatLangError::write_failed(None))?;
+        }
+    }
+    debug!("Input ended, stopping worker");
+    Ok(())
+}
+
+fn answer(line: &str, session: &mut LangDetector) -> Option<Response> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => return Some(error_response(id, INVALID_REQUEST, "Expected jsonrpc 2.0")),
+        Err(e) => return Some(error_response(id, INVALID_REQUEST, e.to_string())),
+    };
+
+    debug!("Handling request {}", request.method);
+    let result = call(&request.method, request.params, session);
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(error) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        },
+    })
+}
+
+fn call(method: &str, params: Value, session: &mut LangDetector) -> Result<Value, RpcError> {
+    match method {
+        "detect" => {
+            let text: String = param(params, "text")?;
+            Ok(json!(session.detect(&text)))
+        }
+        "detect_batch" => {
+            let texts: Vec<String> = param(params, "texts")?;
+            Ok(json!(session.detect_many(&texts)))
+        }
+        "detect_script" => {
+            let text: String = param(params, "text")?;
+            Ok(json!(whatlang::detect_script(&text).map(|s| s.to_string())))
+        }
+        "process" => {
+            let request: ProcessRequest = serde_json::from_value(params)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+            request
+                .process()
+                .map(|report| json!(report))
+                .map_err(|e| RpcError {
+                    data: Some(json!({"code": e.code()})),
+                    ..RpcError::new(PROCESS_ERROR, e.to_string())
+                })
+        }
+        "options" => {
+            if !params.is_null() {
+                let config: DetectorConfig =
+                    serde_json::from_value(merge(json!(session.config()), params)?)
+                        .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                *session = config
+                    .build()
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+            }
+            Ok(json!(session.config()))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+/// Returns the single parameter `name`, given by name in an object or by position in an array.
+fn param<T: serde::de::DeserializeOwned>(params: Value, name: &str) -> Result<T, RpcError> {
+    let value = match params {
+        Value::Object(mut object) => object.remove(name),
+        Value::Array(array) => array.into_iter().next(),
+        _ => None,
+    };
+    let value =
+        value.ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Missing param '{}'", name)))?;
+    serde_json::from_value(value)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid param '{}': {}", name, e)))
+}
+
+fn merge(mut options: Value, params: Value) -> Result<Value, RpcError> {
+    match (options.as_object_mut(), params) {
+        (Some(options_object), Value::Object(params)) => {
+            options_object.extend(params);
+            Ok(options)
+        }
+        _ => Err(RpcError::new(INVALID_PARAMS, "Expected an object")),
+    }
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Response {
+    Response {
+        jsonrpc: "2.0",
+        id,
+        result: None,
+        error: Some(RpcError::new(code, message)),
+    }
+}
@@ -1,6 +1,9 @@
 use rand::random;
-use std::io::Write;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
 
 #[test]
 fn cli_with_stdin_works() {
@@ -45,6 +48,29 @@ fn cli_with_stdin_returns_err_for_invalid_utf8_input() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn cli_with_stdin_lines_works() {
+    let mut cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--stdin")
+        .arg("--lines")
+        .spawn()
+        .unwrap();
+    cmd.stdin
+        .as_mut()
+        .unwrap()
+        .write_all(
+            b"Hallo Welt, wie geht es dir heute?\nabc\xff\n123\nThis is an English sentence.\n",
+        )
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), LINES_EXPECTED);
+}
+
 #[test]
 fn cli_with_stdin_json_works() {
     let mut cmd = Command::new("target/debug/whatlang-cli")
@@ -120,6 +146,83 @@ fn cli_with_arg_json_works() {
     assert_eq!(output_str.trim(), SENTENCE_JSON_EXPECTED);
 }
 
+#[test]
+fn cli_with_arg_and_detector_options_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--json")
+        .arg("--denylist")
+        .arg("deu")
+        .arg("--min-confidence")
+        .arg("0.5")
+        .arg(DETECTOR_OPTIONS_JSON)
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), DETECTOR_OPTIONS_EXPECTED);
+}
+
+#[test]
+fn cli_with_arg_and_ensemble_works() {
+    let profiles = std::env::temp_dir().join(format!("whatlang-cli-{}.json", random::<u32>()));
+    let status = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::null())
+        .arg("train")
+        .arg("--out-file")
+        .arg(&profiles)
+        .arg("--name")
+        .arg("gsw=Swiss German")
+        .arg("tests/dialects.jsonl")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--ensemble")
+        .arg("--voters")
+        .arg("whatlang,profiles=2")
+        .arg("--profiles")
+        .arg(&profiles)
+        .arg("Mir händ am Samschtig es Fescht gmacht und alli sind cho.")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), ENSEMBLE_EXPECTED);
+    std::fs::remove_file(profiles).unwrap();
+}
+
+#[test]
+fn cli_with_arg_and_method_ensemble_works() {
+    let output = Command::new("target/debug/whatlang-cli")
+        .arg("--ensemble")
+        .arg("Das ist ein deutscher Satz.")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["Ok"]["language"], "German");
+    let voters: Vec<&str> = result["Ok"]["votes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vote| vote["voter"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        voters,
+        ["whatlang-combined", "whatlang-trigram", "whatlang-alphabet"]
+    );
+}
+
 #[test]
 fn cli_with_files_works() {
     let cmd = Command::new("target/debug/whatlang-cli")
@@ -150,6 +253,47 @@ fn cli_with_files_returns_err_if_the_every_given_file_throws_an_error() {
 
     let output = cmd.wait_with_output().unwrap();
     assert!(!output.status.success());
+    assert_eq!(output.status.code(), Some(8));
+}
+
+#[test]
+fn cli_exits_with_distinct_codes_for_invalid_input() {
+    let run = |args: &[&str], stdin: &[u8]| {
+        let mut cmd = Command::new("target/debug/whatlang-cli")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .args(args)
+            .spawn()
+            .unwrap();
+        cmd.stdin.as_mut().unwrap().write_all(stdin).unwrap();
+        let output = cmd.wait_with_output().unwrap();
+        (
+            output.status.code(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    let (code, stderr) = run(&["--json", r#"["a", 1]"#], b"");
+    assert_eq!(code, Some(5));
+    assert!(stderr.contains("Invalid JSON in input at line 1 column 7"));
+    let (code, stderr) = run(&["--stdin"], b"abc\xff");
+    assert_eq!(code, Some(4));
+    assert!(stderr.contains("Invalid UTF-8 in input at byte offset 3"));
+    let (code, _) = run(&["--stdin", "--email"], b"");
+    assert_eq!(code, Some(6));
+    let (code, stderr) = run(&["--allowlist", "xyz", "text"], b"");
+    assert_eq!(code, Some(10));
+    assert!(stderr.contains("Unknown language code 'xyz'"));
+    let (code, stderr) = run(&["eval", "missing.jsonl"], b"");
+    assert_eq!(code, Some(3));
+    assert!(stderr.contains("Failed to read file \"missing.jsonl\""));
+    let (code, stderr) = run(&["bench"], b"");
+    assert_eq!(code, Some(8));
+    assert!(stderr.contains("The corpus contains no texts"));
+    let (code, stderr) = run(&["--backend", "unknown", "text"], b"");
+    assert_eq!(code, Some(10));
+    assert!(stderr.contains("Unknown backend 'unknown', expected one of: profiles, whatlang, whatlang-alphabet, whatlang-combined, whatlang-trigram"));
 }
 
 #[test]
@@ -187,6 +331,701 @@ fn cli_with_json_files_works() {
     assert_eq!(output_str.trim(), JSON_FILE_EXPECTED.trim());
 }
 
+#[test]
+fn cli_with_email_file_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--email")
+        .arg("-f")
+        .arg("tests/message.eml")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), EMAIL_FILE_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_stdin_mbox_works() {
+    let mut cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("--stdin")
+        .arg("--mbox")
+        .spawn()
+        .unwrap();
+    {
+        let stdin = cmd.stdin.as_mut().expect("failed to open stdin");
+        stdin
+            .write_all(&std::fs::read("tests/mailbox.mbox").unwrap())
+            .expect("failed to write to stdin");
+    }
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), MBOX_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_docx_file_and_sections_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--sections")
+        .arg("-f")
+        .arg("tests/document.docx")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), DOCX_SECTIONS_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_epub_file_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("-f")
+        .arg("tests/book.epub")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), EPUB_FILE_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_oversized_document_entry_returns_err() {
+    let path = std::env::temp_dir().join(format!("whatlang-cli-{}.docx", random::<u32>()));
+    {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        zip.start_file(
+            "word/document.xml",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        let chunk = vec![b' '; 1024 * 1024];
+        for _ in 0..=64 {
+            zip.write_all(&chunk).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    let output = Command::new("target/debug/whatlang-cli")
+        .arg("-f")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(output.status.code(), Some(8));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("word/document.xml in document exceeds"));
+}
+
+#[test]
+fn cli_with_files_and_sentence_segments_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--segment")
+        .arg("sentence")
+        .arg("-f")
+        .arg("tests/mixed.txt")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), SEGMENT_FILE_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_arg_sentences_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--sentences")
+        .arg(SENTENCES)
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), SENTENCES_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_files_and_distribution_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--distribution")
+        .arg("-f")
+        .arg("tests/mixed.txt")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), DISTRIBUTION_FILE_EXPECTED.trim());
+}
+
+#[test]
+fn cli_with_json_files_and_summary_only_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("--summary-only")
+        .arg("--json")
+        .arg("-f")
+        .arg("tests/texts.json")
+        .arg("-f")
+        .arg("i/do/not/exist.txt")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), SUMMARY_EXPECTED.trim());
+}
+
+#[test]
+fn cli_split_with_jsonl_works() {
+    let out_dir = std::env::temp_dir().join(format!("whatlang-cli-split-{}", random::<u32>()));
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("split")
+        .arg("--format")
+        .arg("jsonl")
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .arg("tests/texts.jsonl")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), SPLIT_EXPECTED.trim());
+    let undetermined = std::fs::read_to_string(out_dir.join("und.jsonl")).unwrap();
+    assert_eq!(undetermined, "{\"id\": 6, \"text\": \"123456789\"}\n");
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn cli_split_skips_files_whose_name_is_taken() {
+    let dir = std::env::temp_dir().join(format!("whatlang-cli-split-{}", random::<u32>()));
+    for input in ["a", "b"] {
+        std::fs::create_dir_all(dir.join(input)).unwrap();
+        std::fs::write(dir.join(input).join("text.txt"), SENTENCE).unwrap();
+    }
+    let output = Command::new("target/debug/whatlang-cli")
+        .arg("split")
+        .arg("--format")
+        .arg("files")
+        .arg("--out-dir")
+        .arg(dir.join("out"))
+        .arg(dir.join("a/text.txt"))
+        .arg(dir.join("b/text.txt"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["buckets"]["deu"], 1);
+    assert_eq!(report["skipped"], 1);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("b/text.txt"));
+    assert!(stderr.contains("File exists"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_split_rejects_codes_that_escape_the_out_dir() {
+    let dir = std::env::temp_dir().join(format!("whatlang-cli-split-{}", random::<u32>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let profiles = dir.join("profiles.json");
+    std::fs::write(
+        &profiles,
+        r#"{"profiles": [{"code": "../escaped", "name": "Escaped", "script": "Latin", "examples": 1, "trigrams": {" a ": 1.0}}]}"#,
+    )
+    .unwrap();
+    let output = Command::new("target/debug/whatlang-cli")
+        .arg("split")
+        .arg("--profiles")
+        .arg(&profiles)
+        .arg("--out-dir")
+        .arg(dir.join("out"))
+        .arg("tests/text.txt")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(10));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid language code '../escaped'"));
+    assert!(!dir.join("escaped.jsonl").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_filter_with_jsonl_works() {
+    let mut cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("filter")
+        .arg("--format")
+        .arg("jsonl")
+        .arg("--lang")
+        .arg("DEU,eng")
+        .arg("--min-confidence")
+        .arg("0.8")
+        .spawn()
+        .unwrap();
+    {
+        let stdin = cmd.stdin.as_mut().expect("failed to open stdin");
+        stdin
+            .write_all(b"{\"id\": -1, \"text\": \"\xff\"}\n")
+            .expect("failed to write to stdin");
+        stdin
+            .write_all(&std::fs::read("tests/texts.jsonl").unwrap())
+            .expect("failed to write to stdin");
+    }
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    let ids: Vec<&str> = output_str.lines().map(|line| &line[..8]).collect();
+    assert_eq!(ids, vec!["{\"id\": 0", "{\"id\": 5"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid UTF-8 in input at byte offset 20. Skipping item"));
+}
+
+#[test]
+fn cli_filter_with_invert_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("filter")
+        .arg("--format")
+        .arg("jsonl")
+        .arg("--invert")
+        .arg("tests/texts.jsonl")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str, "{\"id\": 6, \"text\": \"123456789\"}\n");
+}
+
+#[test]
+fn cli_eval_with_csv_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("eval")
+        .arg("--format")
+        .arg("csv")
+        .arg("tests/labeled.csv")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), EVAL_EXPECTED);
+}
+
+#[test]
+fn cli_train_and_profiles_work() {
+    let profiles = std::env::temp_dir().join(format!("whatlang-cli-{}.json", random::<u32>()));
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("train")
+        .arg("--out-file")
+        .arg(&profiles)
+        .arg("--name")
+        .arg("gsw=Swiss German")
+        .arg("tests/dialects.jsonl")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), TRAIN_EXPECTED);
+
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("--profiles")
+        .arg(&profiles)
+        .arg("--json")
+        .arg(r#"["Mir händ am Samschtig es Fescht gmacht und alli sind cho.", "Wir haben am Samstag ein Fest gemacht und alle sind gekommen.", "This is an English sentence about the weather."]"#)
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), PROFILES_EXPECTED);
+    std::fs::remove_file(profiles).unwrap();
+}
+
+#[test]
+fn cli_composition_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("--composition")
+        .arg("--json")
+        .arg(r#"["Я купил новый iPhone в магазине Apple 😀👍🏽!", "🇨🇭🇨🇭 123"]"#)
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), COMPOSITION_EXPECTED);
+}
+
+#[test]
+fn cli_normalization_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("--normalize")
+        .arg("nfkc")
+        .arg("--strip-mentions")
+        .arg("--strip-hashtags")
+        .arg("--strip-emoji")
+        .arg("--strip-numbers")
+        .arg("--collapse-whitespace")
+        .arg("@anna #sonntag 😀 Wir gehen   heute um 15:30 in den Park")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), NORMALIZATION_EXPECTED);
+}
+
+#[test]
+fn cli_min_length_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("--min-chars")
+        .arg("5")
+        .arg("--min-words")
+        .arg("2")
+        .arg("--summary")
+        .arg("--json")
+        .arg(r#"["ok", "Danke!", "Das ist ein deutscher Satz."]"#)
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), MIN_LENGTH_EXPECTED);
+}
+
+#[test]
+fn cli_sample_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("--max-bytes")
+        .arg("240")
+        .arg("--sample")
+        .arg("spread")
+        .arg("--chunks")
+        .arg("3")
+        .arg("--file")
+        .arg("tests/mixed.txt")
+        .arg("--file")
+        .arg("tests/text.txt")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert_eq!(output_str.trim(), SAMPLE_EXPECTED);
+}
+
+#[test]
+fn cli_sample_rejects_text_and_stdin() {
+    for input in ["text", "--stdin"] {
+        let output = Command::new("target/debug/whatlang-cli")
+            .arg("--max-bytes")
+            .arg("240")
+            .arg(input)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("cannot be used with"));
+    }
+}
+
+#[test]
+fn cli_bench_works() {
+    let cmd = Command::new("target/debug/whatlang-cli")
+        .stdout(Stdio::piped())
+        .arg("bench")
+        .arg("--format")
+        .arg("jsonl")
+        .arg("--iterations")
+        .arg("2")
+        .arg("--buckets")
+        .arg("64")
+        .arg("--output")
+        .arg("json")
+        .arg("tests/texts.jsonl")
+        .spawn()
+        .unwrap();
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    assert!(output_str.contains("\"texts\": 7,"));
+    assert!(output_str.contains("\"iterations\": 2,"));
+    let runs: Vec<&str> = output_str
+        .lines()
+        .filter(|line| line.contains("\"bucket\""))
+        .map(str::trim)
+        .collect();
+    assert_eq!(
+        runs,
+        vec![
+            "\"bucket\": \"all\",",
+            "\"bucket\": \"0-64\",",
+            "\"bucket\": \"65+\",",
+        ]
+    );
+
+    let output = Command::new("target/debug/whatlang-cli")
+        .arg("bench")
+        .arg("--format")
+        .arg("jsonl")
+        .arg("--iterations")
+        .arg("1")
+        .arg("--buckets")
+        .arg("64")
+        .arg("--methods")
+        .arg("trigram,alphabet")
+        .arg("--output")
+        .arg("json")
+        .arg("tests/texts.jsonl")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let benchmark: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let runs: Vec<(&str, &str)> = benchmark["runs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|run| {
+            (
+                run["method"].as_str().unwrap(),
+                run["bucket"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        runs,
+        [
+            ("trigram", "all"),
+            ("trigram", "0-64"),
+            ("trigram", "65+"),
+            ("alphabet", "all"),
+            ("alphabet", "0-64"),
+            ("alphabet", "65+"),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_serve_works() {
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let mut server = Command::new("target/debug/whatlang-cli")
+        .arg("serve")
+        .arg("--address")
+        .arg(&address)
+        .arg("--max-body-bytes")
+        .arg("1000")
+        .spawn()
+        .unwrap();
+
+    let request = |method: &str, path: &str, body: &str| {
+        let mut stream = (0..50)
+            .find_map(|_| {
+                TcpStream::connect(&address)
+                    .inspect_err(|_| sleep(Duration::from_millis(100)))
+                    .ok()
+            })
+            .expect("failed to connect to server");
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    };
+
+    let (status, body) = request("GET", "/ready", "");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(body, r#"{"status":"ready"}"#);
+    let (status, body) = request("POST", "/detect", &format!("{:?}", SENTENCE));
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(
+        body,
+        r#"{"Ok":{"confidence":1.0,"is_reliable":true,"language":"German","script":"Latin"}}"#
+    );
+    let (status, body) = request("POST", "/detect?pretty=false", &format!("{:?}", SENTENCE));
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(body.contains(r#""language":"German""#));
+    let (status, body) = request("POST", "/detect/batch", r#"["123456789"]"#);
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(
+        body,
+        r#"[{"Error":{"code":"detection_failed","message":"Failed to detect language"}}]"#
+    );
+    let (status, _) = request("POST", "/detect", &"a".repeat(1001));
+    assert_eq!(status, "HTTP/1.1 413 Payload Too Large");
+    let (status, _) = request("GET", "/detect", "");
+    assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+
+    Command::new("kill")
+        .arg("-TERM")
+        .arg(server.id().to_string())
+        .status()
+        .unwrap();
+    assert!(server.wait().unwrap().success());
+}
+
+#[test]
+fn cli_worker_works() {
+    let mut cmd = Command::new("target/debug/whatlang-cli")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .arg("--worker")
+        .spawn()
+        .unwrap();
+    {
+        let stdin = cmd.stdin.as_mut().expect("failed to open stdin");
+        stdin
+            .write_all(WORKER_REQUESTS.as_bytes())
+            .expect("failed to write to stdin");
+        stdin
+            .write_all(b"{\"jsonrpc\": \"2.0\", \"id\": 7, \"method\": \"detect\", \"params\": [\"\xff\"]}\n")
+            .expect("failed to write to stdin");
+        stdin
+            .write_all(WORKER_REQUESTS.lines().next().unwrap().as_bytes())
+            .expect("failed to write to stdin");
+    }
+
+    let output = cmd.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output_str = String::from_utf8(output.stdout).expect("Output is not valid UTF-8");
+    let mut lines: Vec<_> = output_str.trim().lines().collect();
+    let options: serde_json::Value = serde_json::from_str(lines.remove(5)).unwrap();
+    assert_eq!(options["id"], 4);
+    assert_eq!(options["result"]["reliable_only"], true);
+    assert_eq!(lines.join("\n"), WORKER_EXPECTED);
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_connect_works_with_and_without_daemon() {
+    let socket = std::env::temp_dir().join(format!("whatlang-cli-{}.sock", random::<u32>()));
+    let detect = || {
+        Command::new("target/debug/whatlang-cli")
+            .stdout(Stdio::piped())
+            .arg("--connect")
+            .arg(&socket)
+            .arg(SENTENCE)
+            .output()
+            .unwrap()
+    };
+
+    let output = detect();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        SENTENCE_EXPECTED
+    );
+
+    let mut daemon = Command::new("target/debug/whatlang-cli")
+        .arg("daemon")
+        .arg("--socket")
+        .arg(&socket)
+        .spawn()
+        .unwrap();
+    for _ in 0..50 {
+        if socket.exists() {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    assert!(socket.exists());
+    let mode = std::os::unix::fs::PermissionsExt::mode(&socket.metadata().unwrap().permissions());
+    assert_eq!(mode & 0o777, 0o600);
+
+    let output = detect();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        SENTENCE_EXPECTED
+    );
+    let output = Command::new("target/debug/whatlang-cli")
+        .arg("--connect")
+        .arg(&socket)
+        .arg("--json")
+        .arg(r#"["a", 1]"#)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("The daemon failed: Invalid JSON in input at line 1 column 7"));
+
+    Command::new("kill")
+        .arg("-TERM")
+        .arg(daemon.id().to_string())
+        .status()
+        .unwrap();
+    assert!(daemon.wait().unwrap().success());
+    assert!(!socket.exists());
+}
+
 const SENTENCE: &str = "Trigramme sind ein Spezialfall des n-Gramms, wobei n gleich 3 ist. Sie werden häufig in der Verarbeitung natürlicher Sprache zur statistischen Analyse von Texten und in der Kryptographie zur Kontrolle und Verwendung von Chiffren und Codes verwendet.";
 
 const SENTENCE_EXPECTED: &str = r#"{
@@ -427,3 +1266,661 @@ const JSON_FILE_EXPECTED: &str = r#"[
     ]
   }
 ]"#;
+
+const EMAIL_FILE_EXPECTED: &str = r#"[
+  {
+    "file": "tests/message.eml",
+    "message_ids": [
+      "de-1@example.com"
+    ],
+    "results": [
+      {
+        "Ok": {
+          "confidence": 1.0,
+          "is_reliable": true,
+          "language": "German",
+          "script": "Latin"
+        }
+      }
+    ]
+  }
+]"#;
+
+const MBOX_EXPECTED: &str = r#"[
+  {
+    "message_id": "de-1@example.com",
+    "result": {
+      "Ok": {
+        "confidence": 1.0,
+        "is_reliable": true,
+        "language": "German",
+        "script": "Latin"
+      }
+    }
+  },
+  {
+    "message_id": "en-1@example.com",
+    "result": {
+      "Ok": {
+        "confidence": 1.0,
+        "is_reliable": true,
+        "language": "English",
+        "script": "Latin"
+      }
+    }
+  }
+]"#;
+
+const DOCX_SECTIONS_EXPECTED: &str = r#"[
+  {
+    "file": "tests/document.docx",
+    "results": [
+      {
+        "Ok": {
+          "confidence": 0.17561119752204693,
+          "is_reliable": false,
+          "language": "German",
+          "script": "Latin"
+        }
+      }
+    ],
+    "sections": [
+      {
+        "result": {
+          "Ok": {
+            "confidence": 1.0,
+            "is_reliable": true,
+            "language": "German",
+            "script": "Latin"
+          }
+        },
+        "title": "Deutsch"
+      },
+      {
+        "result": {
+          "Ok": {
+            "confidence": 1.0,
+            "is_reliable": true,
+            "language": "English",
+            "script": "Latin"
+          }
+        },
+        "title": "English"
+      }
+    ]
+  }
+]"#;
+
+const EPUB_FILE_EXPECTED: &str = r#"[
+  {
+    "file": "tests/book.epub",
+    "results": [
+      {
+        "Ok": {
+          "confidence": 0.3730512123751442,
+          "is_reliable": false,
+          "language": "German",
+          "script": "Latin"
+        }
+      }
+    ]
+  }
+]"#;
+
+const SEGMENT_FILE_EXPECTED: &str = r#"[
+  {
+    "file": "tests/mixed.txt",
+    "results": [
+      {
+        "Ok": {
+          "confidence": 0.32118515780751145,
+          "is_reliable": false,
+          "language": "German",
+          "script": "Latin"
+        }
+      }
+    ],
+    "segments": [
+      {
+        "distribution": [
+          {
+            "characters": 248,
+            "language": "German",
+            "ratio": 0.36046511627906974
+          },
+          {
+            "characters": 226,
+            "language": "Turkish",
+            "ratio": 0.32848837209302323
+          },
+          {
+            "characters": 214,
+            "language": "English",
+            "ratio": 0.311046511627907
+          }
+        ],
+        "spans": [
+          {
+            "end": 251,
+            "result": {
+              "Ok": {
+                "confidence": 1.0,
+                "is_reliable": true,
+                "language": "German",
+                "script": "Latin"
+              }
+            },
+            "start": 0
+          },
+          {
+            "end": 467,
+            "result": {
+              "Ok": {
+                "confidence": 1.0,
+                "is_reliable": true,
+                "language": "English",
+                "script": "Latin"
+              }
+            },
+            "start": 252
+          },
+          {
+            "end": 708,
+            "result": {
+              "Ok": {
+                "confidence": 0.8363329119074595,
+                "is_reliable": false,
+                "language": "Turkish",
+                "script": "Latin"
+              }
+            },
+            "start": 469
+          }
+        ]
+      }
+    ]
+  }
+]"#;
+
+const SENTENCES: &str = "Trigrams are a special case of the n-gram. 三段论是 n-gram 的一种特例。「在自然语言处理中，它们常用于对文本进行统计分析。」";
+
+const SENTENCES_EXPECTED: &str = r#"[
+  {
+    "end": 42,
+    "result": {
+      "Ok": {
+        "confidence": 0.533870929769072,
+        "is_reliable": false,
+        "language": "English",
+        "script": "Latin"
+      }
+    },
+    "start": 0
+  },
+  {
+    "end": 81,
+    "result": {
+      "Ok": {
+        "confidence": 1.0,
+        "is_reliable": true,
+        "language": "Mandarin",
+        "script": "Mandarin"
+      }
+    },
+    "start": 43
+  },
+  {
+    "end": 159,
+    "result": {
+      "Ok": {
+        "confidence": 1.0,
+        "is_reliable": true,
+        "language": "Mandarin",
+        "script": "Mandarin"
+      }
+    },
+    "start": 81
+  }
+]"#;
+
+const DISTRIBUTION_FILE_EXPECTED: &str = r#"[
+  {
+    "distribution": [
+      {
+        "characters": 248,
+        "language": "German",
+        "ratio": 0.36046511627906974
+      },
+      {
+        "characters": 226,
+        "language": "Turkish",
+        "ratio": 0.32848837209302323
+      },
+      {
+        "characters": 214,
+        "language": "English",
+        "ratio": 0.311046511627907
+      }
+    ],
+    "file": "tests/mixed.txt",
+    "results": [
+      {
+        "Ok": {
+          "confidence": 0.32118515780751145,
+          "is_reliable": false,
+          "language": "German",
+          "script": "Latin"
+        }
+      }
+    ]
+  }
+]"#;
+
+const SUMMARY_EXPECTED: &str = r#"{
+  "characters": 2191,
+  "errors": 0,
+  "items": 11,
+  "languages": [
+    {
+      "characters": 249,
+      "count": 1,
+      "language": "German",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 168,
+      "count": 1,
+      "language": "Arabic",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 209,
+      "count": 1,
+      "language": "Russian",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 227,
+      "count": 1,
+      "language": "Turkish",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 219,
+      "count": 1,
+      "language": "Bulgarian",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 215,
+      "count": 1,
+      "language": "English",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 73,
+      "count": 1,
+      "language": "Mandarin",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 229,
+      "count": 1,
+      "language": "Italian",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 206,
+      "count": 1,
+      "language": "Polish",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 198,
+      "count": 1,
+      "language": "Czech",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    },
+    {
+      "characters": 198,
+      "count": 1,
+      "language": "Slovak",
+      "mean_confidence": 1.0,
+      "median_confidence": 1.0,
+      "reliability_ratio": 1.0
+    }
+  ],
+  "reliability_ratio": 1.0,
+  "scripts": [
+    {
+      "characters": 1522,
+      "count": 7,
+      "script": "Latin"
+    },
+    {
+      "characters": 428,
+      "count": 2,
+      "script": "Cyrillic"
+    },
+    {
+      "characters": 168,
+      "count": 1,
+      "script": "Arabic"
+    },
+    {
+      "characters": 73,
+      "count": 1,
+      "script": "Mandarin"
+    }
+  ],
+  "skipped": 1,
+  "too_short": 0
+}"#;
+
+const SPLIT_EXPECTED: &str = r#"{
+  "buckets": {
+    "ara": 1,
+    "bul": 1,
+    "deu": 1,
+    "eng": 1,
+    "rus": 1,
+    "tur": 1,
+    "und": 1
+  },
+  "skipped": 0
+}"#;
+
+const EVAL_EXPECTED: &str = r#"examples: 6, correct: 4, accuracy: 0.6667, macro F1: 0.5556, skipped: 0
+
+language  support  predicted  precision  recall      f1
+bul             0          1     0.0000  0.0000  0.0000
+deu             1          1     1.0000  1.0000  1.0000
+eng             2          1     1.0000  0.5000  0.6667
+rus             2          1     1.0000  0.5000  0.6667
+tur             1          1     1.0000  1.0000  1.0000
+und             0          1     0.0000  0.0000  0.0000
+
+confusion matrix (rows: expected, columns: detected)
+          bul  deu  eng  rus  tur  und
+deu         0    1    0    0    0    0
+eng         0    0    1    0    0    1
+rus         1    0    0    1    0    0
+tur         0    0    0    0    1    0"#;
+
+const WORKER_REQUESTS: &str = r#"{"jsonrpc": "2.0", "id": 1, "method": "detect", "params": {"text": "Trigrams are a special case of the n-gram, where n equals 3."}}
+not json
+{"jsonrpc": "2.0", "id": 2, "method": "detect_batch", "params": [["Триграммы - это частный случай n-грамм.", "123456789"]]}
+{"jsonrpc": "2.0", "method": "detect", "params": ["A notification is not answered"]}
+{"jsonrpc": "2.0", "id": 3, "method": "detect_script", "params": ["Триграммы"]}
+{"jsonrpc": "2.0", "id": null, "method": "detect_script", "params": ["Триграммы"]}
+{"jsonrpc": "2.0", "id": 4, "method": "options", "params": {"reliable_only": true}}
+{"jsonrpc": "2.0", "id": 5, "method": "translate", "params": ["Hello"]}
+{"jsonrpc": "2.0", "id": 6, "method": "detect"}
+"#;
+
+const WORKER_EXPECTED: &str = r#"{"jsonrpc":"2.0","id":1,"result":{"Ok":{"confidence":1.0,"is_reliable":true,"language":"English","script":"Latin"}}}
+{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"expected ident at line 1 column 2"}}
+{"jsonrpc":"2.0","id":2,"result":[{"Ok":{"confidence":0.24651439661238544,"is_reliable":false,"language":"Russian","script":"Cyrillic"}},{"Error":{"code":"detection_failed","message":"Failed to detect language"}}]}
+{"jsonrpc":"2.0","id":3,"result":"Cyrillic"}
+{"jsonrpc":"2.0","id":null,"result":"Cyrillic"}
+{"jsonrpc":"2.0","id":5,"error":{"code":-32601,"message":"Unknown method 'translate'"}}
+{"jsonrpc":"2.0","id":6,"error":{"code":-32602,"message":"Missing param 'text'"}}
+{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Invalid UTF-8 in request at byte offset 60"}}
+{"jsonrpc":"2.0","id":1,"result":{"Ok":{"confidence":1.0,"is_reliable":true,"language":"English","script":"Latin"}}}"#;
+
+const DETECTOR_OPTIONS_JSON: &str = "[\"Trigramme sind ein Spezialfall des n-Gramms, wobei n gleich 3 ist. Sie werden häufig in der Verarbeitung natürlicher Sprache zur statistischen Analyse von Texten und in der Kryptographie zur Kontrolle und Verwendung von Chiffren und Codes verwendet.\", \"Триграммы - это частный случай n-грамм.\"]";
+
+const DETECTOR_OPTIONS_EXPECTED: &str = r#"[
+  {
+    "Ok": {
+      "confidence": 1.0,
+      "is_reliable": true,
+      "language": "Dutch",
+      "script": "Latin"
+    }
+  },
+  {
+    "Error": {
+      "code": "below_threshold",
+      "message": "Detected Russian with confidence 0.24651439661238544 (unreliable), which is below the threshold"
+    }
+  }
+]"#;
+
+const LINES_EXPECTED: &str = r#"{"Ok":{"confidence":0.28481976618986404,"is_reliable":false,"language":"German","script":"Latin"}}
+{"Error":{"code":"invalid_utf8","message":"Invalid UTF-8 in input at byte offset 38"}}
+{"Error":{"code":"detection_failed","message":"Failed to detect language"}}
+{"Ok":{"confidence":0.9272165353520533,"is_reliable":true,"language":"English","script":"Latin"}}"#;
+
+const ENSEMBLE_EXPECTED: &str = r#"{
+  "Ok": {
+    "agreement": 0.6666666666666666,
+    "code": "gsw",
+    "confidence": 0.16292868521187875,
+    "is_reliable": true,
+    "language": "Swiss German",
+    "script": "Latin",
+    "votes": [
+      {
+        "confidence": 0.48232454963745425,
+        "language": "German",
+        "voter": "whatlang",
+        "weight": 1.0
+      },
+      {
+        "confidence": 0.24439302781781813,
+        "language": "Swiss German",
+        "voter": "profiles",
+        "weight": 2.0
+      }
+    ]
+  }
+}"#;
+
+const TRAIN_EXPECTED: &str = r#"[
+  {
+    "code": "deu",
+    "examples": 6,
+    "name": "German",
+    "script": "Latin",
+    "trigrams": 300
+  },
+  {
+    "code": "gsw",
+    "examples": 6,
+    "name": "Swiss German",
+    "script": "Latin",
+    "trigrams": 300
+  }
+]"#;
+
+const PROFILES_EXPECTED: &str = r#"[
+  {
+    "Ok": {
+      "code": "gsw",
+      "confidence": 0.24439302781781813,
+      "is_reliable": true,
+      "language": "Swiss German",
+      "script": "Latin"
+    }
+  },
+  {
+    "Ok": {
+      "confidence": 1.0,
+      "is_reliable": true,
+      "language": "German",
+      "script": "Latin"
+    }
+  },
+  {
+    "Ok": {
+      "confidence": 1.0,
+      "is_reliable": true,
+      "language": "English",
+      "script": "Latin"
+    }
+  }
+]"#;
+
+const COMPOSITION_EXPECTED: &str = r#"[
+  {
+    "Ok": {
+      "composition": {
+        "characters": 42,
+        "digits": 0,
+        "emoji": 2,
+        "punctuation": 1,
+        "scripts": [
+          {
+            "characters": 20,
+            "ratio": 0.6451612903225806,
+            "script": "Cyrillic"
+          },
+          {
+            "characters": 11,
+            "ratio": 0.3548387096774194,
+            "script": "Latin"
+          }
+        ]
+      },
+      "confidence": 1.0,
+      "is_reliable": true,
+      "language": "Russian",
+      "script": "Cyrillic"
+    }
+  },
+  {
+    "Error": {
+      "code": "detection_failed",
+      "composition": {
+        "characters": 8,
+        "digits": 3,
+        "emoji": 2,
+        "punctuation": 0,
+        "scripts": []
+      },
+      "message": "Failed to detect language"
+    }
+  }
+]"#;
+
+const NORMALIZATION_EXPECTED: &str = r#"{
+  "Ok": {
+    "confidence": 0.3127393647844444,
+    "is_reliable": false,
+    "language": "German",
+    "normalized_characters": 30,
+    "script": "Latin"
+  }
+}"#;
+
+const MIN_LENGTH_EXPECTED: &str = r#"{
+  "results": [
+    {
+      "TooShort": {
+        "characters": 2,
+        "words": 1
+      }
+    },
+    {
+      "TooShort": {
+        "characters": 6,
+        "words": 1
+      }
+    },
+    {
+      "Ok": {
+        "confidence": 1.0,
+        "is_reliable": true,
+        "language": "German",
+        "script": "Latin"
+      }
+    }
+  ],
+  "summary": {
+    "characters": 35,
+    "errors": 0,
+    "items": 3,
+    "languages": [
+      {
+        "characters": 27,
+        "count": 1,
+        "language": "German",
+        "mean_confidence": 1.0,
+        "median_confidence": 1.0,
+        "reliability_ratio": 1.0
+      }
+    ],
+    "reliability_ratio": 1.0,
+    "scripts": [
+      {
+        "characters": 27,
+        "count": 1,
+        "script": "Latin"
+      }
+    ],
+    "skipped": 0,
+    "too_short": 2
+  }
+}"#;
+
+const SAMPLE_EXPECTED: &str = r#"[
+  {
+    "file": "tests/mixed.txt",
+    "results": [
+      {
+        "Ok": {
+          "confidence": 1.0,
+          "is_reliable": true,
+          "language": "English",
+          "script": "Latin"
+        }
+      }
+    ],
+    "sample": {
+      "bytes": 239,
+      "chunks": 3,
+      "file_bytes": 709,
+      "strategy": "spread"
+    }
+  },
+  {
+    "file": "tests/text.txt",
+    "results": [
+      {
+        "Ok": {
+          "confidence": 1.0,
+          "is_reliable": true,
+          "language": "German",
+          "script": "Latin"
+        }
+      }
+    ],
+    "sample": {
+      "bytes": 239,
+      "chunks": 3,
+      "file_bytes": 251,
+      "strategy": "spread"
+    }
+  }
+]"#;
@@ -0,0 +1,547 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use whatlang_cli::{
+    backends, detect_files, detect_json, detect_string, process_string, register_backend,
+    script_composition, train_profiles, DetectorConfig, FileOptions, Granularity, InputFormat,
+    LabeledFormat, LangDetector, LangInfo, LanguageDetector, LanguageProfiles, Method,
+    NormalizationForm, Report, SampleStrategy, Sampling, SegmentMode, TrainOptions, WhatLangError,
+    WhatLangFromFileResult, WhatLangFromTextResult, WhatLangReport, WhatLangResult,
+    WhatLangResults,
+};
+
+#[test]
+fn detect_string_returns_typed_result() {
+    let result = detect_string("Trigrams are a special case of the n-gram, where n equals 3.");
+    let info = result.info().expect("language should be detected");
+    assert_eq!(info.language(), "English");
+    assert_eq!(info.script(), "Latin");
+    assert_eq!(info.code(), Some("eng"));
+    assert!(info.is_reliable());
+    assert!(result.error().is_none());
+
+    let result = detect_string("123456789");
+    assert!(matches!(result, WhatLangResult::Error(_)));
+    let error = result.error().unwrap();
+    assert_eq!(error.code(), "detection_failed");
+    assert_eq!(error.message(), "Failed to detect language");
+}
+
+#[test]
+fn detect_json_and_files_return_typed_results() {
+    let results =
+        detect_json(r#"["Триграммы - это частный случай n-грамм.", "123456789"]"#).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].info().unwrap().language(), "Russian");
+    assert!(results[1].info().is_none());
+    match detect_json("[\"a\", 1]") {
+        Err(WhatLangError::InvalidJson { line, column, .. }) => assert_eq!((line, column), (1, 7)),
+        other => panic!("expected invalid JSON, got {:?}", other),
+    }
+
+    let options = FileOptions {
+        granularity: Granularity::Sentences,
+        distribution: true,
+        ..FileOptions::default()
+    };
+    let results = detect_files(vec![PathBuf::from("tests/mixed.txt")], &options).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file(), &PathBuf::from("tests/mixed.txt"));
+    assert_eq!(results[0].results().len(), 1);
+    let sentences = &results[0].sentences().unwrap()[0];
+    assert!(sentences.len() > 1);
+    assert!(sentences.windows(2).all(|w| w[0].end() <= w[1].start()));
+    let distribution = results[0].distribution().unwrap();
+    let total: f64 = distribution.iter().map(|share| share.ratio()).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    assert!(results[0].segments().is_none());
+}
+
+#[test]
+fn process_string_returns_a_typed_report() {
+    let report = process_string(
+        r#"["Hallo Welt. Hello world.", "123456789"]"#.to_string(),
+        InputFormat::Json,
+        Granularity::Sentences,
+        Report::ResultsWithSummary,
+    )
+    .unwrap();
+    match report {
+        WhatLangReport::ResultsWithSummary {
+            results: WhatLangResults::Texts(texts),
+            ..
+        } => {
+            assert_eq!(texts.len(), 2);
+            match &texts[0] {
+                WhatLangFromTextResult::Sentences(sentences) => assert_eq!(sentences.len(), 2),
+                other => panic!("expected sentences, got {:?}", other),
+            }
+        }
+        other => panic!("expected results with summary, got {:?}", other),
+    }
+
+    let report = process_string(
+        "Trigrams are a special case of the n-gram, where n equals 3.".to_string(),
+        InputFormat::Plain,
+        Granularity::Text,
+        Report::Results,
+    )
+    .unwrap();
+    match report {
+        WhatLangReport::Results(WhatLangResults::Text(WhatLangFromTextResult::Text(result))) => {
+            assert_eq!(result.info().unwrap().language(), "English")
+        }
+        other => panic!("expected a single result, got {:?}", other),
+    }
+}
+
+#[test]
+fn lang_detector_applies_its_config() {
+    let text = "Trigramme sind ein Spezialfall des n-Gramms, wobei n gleich 3 ist.";
+    let detector = DetectorConfig::new().denylist(["DEU"]).build().unwrap();
+    assert_ne!(detector.detect(text).info().unwrap().language(), "German");
+
+    let detect = |method: Method| {
+        let detector = DetectorConfig::new().method(method).build().unwrap();
+        detector
+            .detect("Das ist ein deutscher Satz.")
+            .info()
+            .cloned()
+            .unwrap()
+    };
+    assert_eq!(detect(Method::Trigram).language(), "German");
+    assert_ne!(detect(Method::Alphabet).language(), "German");
+
+    let russian = "Триграммы - это частный случай n-грамм.".to_string();
+    let detector = DetectorConfig::new().reliable_only(true).build().unwrap();
+    let results = detector.detect_many(&[text.to_string(), russian.clone()]);
+    assert_eq!(results[0].info().unwrap().language(), "German");
+    assert_eq!(results[1].error().unwrap().code(), "below_threshold");
+    let detector = DetectorConfig::new()
+        .allowlist(["eng", "rus"])
+        .build()
+        .unwrap();
+    assert_eq!(
+        detector.detect(&russian).info().unwrap().language(),
+        "Russian"
+    );
+
+    match DetectorConfig::new()
+        .allowlist(["deu"])
+        .denylist(["eng"])
+        .build()
+    {
+        Err(WhatLangError::InvalidConfig { .. }) => {}
+        other => panic!("expected invalid config, got {:?}", other),
+    }
+    let e = DetectorConfig::new()
+        .allowlist(["xyz"])
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+}
+
+#[test]
+fn lang_detector_normalizes_texts() {
+    let detector = DetectorConfig::new()
+        .strip_urls(true)
+        .strip_mentions(true)
+        .strip_hashtags(true)
+        .build()
+        .unwrap();
+    let tweet = "@john_doe #sunday https://example.com/page Wir gehen heute zusammen in den Park";
+    assert_eq!(detector.detect(tweet).info().unwrap().language(), "German");
+    assert_eq!(
+        detector.config().clone(),
+        DetectorConfig::new()
+            .strip_urls(true)
+            .strip_mentions(true)
+            .strip_hashtags(true)
+    );
+
+    let detector = DetectorConfig::new()
+        .normalize(NormalizationForm::Nfkc)
+        .lowercase(true)
+        .strip_emoji(true)
+        .strip_numbers(true)
+        .collapse_whitespace(true)
+        .build()
+        .unwrap();
+    let info = detector
+        .detect("ＷＩＲ  gehen 2024 😀 in den Park")
+        .info()
+        .cloned()
+        .unwrap();
+    assert_eq!(info.normalized_characters(), Some(21));
+    let result = detector.detect("😀 123 👍🏽");
+    assert_eq!(result.error().unwrap().normalized_characters(), Some(0));
+    assert!(detect_string(tweet)
+        .info()
+        .unwrap()
+        .normalized_characters()
+        .is_none());
+}
+
+#[test]
+fn lang_detector_rejects_too_short_texts() {
+    let text = "Trigramme sind ein Spezialfall des n-Gramms, wobei n gleich 3 ist.";
+    let detector = DetectorConfig::new()
+        .min_chars(5)
+        .min_words(2)
+        .build()
+        .unwrap();
+    let result = detector.detect("Danke!");
+    let too_short = result.too_short().expect("text should be too short");
+    assert_eq!((too_short.characters(), too_short.words()), (6, 1));
+    assert!(result.info().is_none() && result.error().is_none());
+    assert!(detector.detect(text).info().is_some());
+}
+
+#[test]
+fn lang_detector_streams_results() {
+    let detector = LangDetector::default();
+    let languages: Vec<String> = detector
+        .detect_iter([
+            "Das ist ein deutscher Satz.",
+            "This is an English sentence.",
+        ])
+        .map(|result| result.info().unwrap().language().to_string())
+        .collect();
+    assert_eq!(languages, ["German", "English"]);
+
+    let input = b"This is an English sentence.\r\nabc\xff\n\nC'est une phrase en fran\xc3\xa7ais.";
+    let results: Vec<_> = detector.detect_lines(Cursor::new(input)).collect();
+    assert_eq!(results.len(), 4);
+    assert_eq!(
+        results[0].as_ref().unwrap().info().unwrap().language(),
+        "English"
+    );
+    match &results[1] {
+        Err(WhatLangError::InvalidUtf8 { offset, .. }) => assert_eq!(*offset, 33),
+        other => panic!("expected invalid UTF-8, got {:?}", other),
+    }
+    assert!(results[2].as_ref().unwrap().error().is_some());
+    assert_eq!(
+        results[3].as_ref().unwrap().info().unwrap().language(),
+        "French"
+    );
+
+    let input = "{\"text\": \"Das ist ein deutscher Satz.\"}\n{\"body\": 1}\n\"This is an English sentence.\"\n";
+    let mut results = detector.detect_jsonl(Cursor::new(input), "text");
+    assert_eq!(
+        results.next().unwrap().unwrap().info().unwrap().language(),
+        "German"
+    );
+    match results.next().unwrap() {
+        Err(WhatLangError::InvalidJson { line, message, .. }) => {
+            assert_eq!(line, 2);
+            assert_eq!(message, "Missing string field 'text'");
+        }
+        other => panic!("expected invalid JSON, got {:?}", other),
+    }
+    assert_eq!(
+        results.next().unwrap().unwrap().info().unwrap().language(),
+        "English"
+    );
+    assert!(results.next().is_none());
+
+    let input = "Das ist ein deutscher Satz. This is an English sentence.\n\nC'est une phrase en français.\n";
+    let spans: Vec<_> = detector
+        .detect_segments(Cursor::new(input), SegmentMode::Sentence)
+        .map(Result::unwrap)
+        .collect();
+    let languages: Vec<_> = spans
+        .iter()
+        .map(|span| span.result().info().unwrap().language())
+        .collect();
+    assert_eq!(languages, ["German", "English", "French"]);
+    assert_eq!(
+        &input[spans[2].start()..spans[2].end()],
+        "C'est une phrase en français."
+    );
+}
+
+/// Detects every text that contains "grüezi" as Swiss German.
+#[derive(Debug)]
+struct Greeting {
+    allowed: bool,
+}
+
+impl LanguageDetector for Greeting {
+    fn detect(&self, text: &str) -> Option<LangInfo> {
+        (self.allowed && text.to_lowercase().contains("grüezi"))
+            .then(|| LangInfo::new("Swiss German", "Latin", 0.9, true))
+    }
+}
+
+#[test]
+fn lang_detector_dispatches_through_registered_backends() {
+    register_backend("greeting", |config: &DetectorConfig| {
+        Ok(Arc::new(Greeting {
+            allowed: config.allows("gsw"),
+        }) as Arc<dyn LanguageDetector>)
+    });
+    assert!(backends().contains(&"greeting".to_string()));
+
+    let detector = DetectorConfig::new().backend("greeting").build().unwrap();
+    let results = detector.detect_many(&["Grüezi mitenand".to_string(), "Hello".to_string()]);
+    assert_eq!(results[0].info().unwrap().language(), "Swiss German");
+    assert_eq!(results[1].error().unwrap().code(), "detection_failed");
+
+    let detector = DetectorConfig::new()
+        .backend("greeting")
+        .min_confidence(0.95)
+        .build()
+        .unwrap();
+    assert_eq!(
+        detector.detect("Grüezi").error().unwrap().code(),
+        "below_threshold"
+    );
+
+    let detector = DetectorConfig::new()
+        .backend("greeting")
+        .denylist(["GSW"])
+        .build()
+        .unwrap();
+    assert!(detector.detect("Grüezi").info().is_none());
+
+    let e = DetectorConfig::new()
+        .backend("unknown")
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+}
+
+#[test]
+fn ensemble_reports_agreement_and_votes() {
+    register_backend("always-ukrainian", |_config: &DetectorConfig| {
+        Ok(Arc::new(AlwaysUkrainian) as Arc<dyn LanguageDetector>)
+    });
+    let detector = DetectorConfig::new()
+        .ensemble([("whatlang", 1.0), ("always-ukrainian", 3.0)])
+        .build()
+        .unwrap();
+    let info = detector
+        .detect("Триграммы - это частный случай n-грамм.")
+        .info()
+        .cloned()
+        .unwrap();
+    assert_eq!(info.language(), "Ukrainian");
+    assert_eq!(info.agreement(), Some(0.75));
+    assert!(info.is_reliable());
+    let votes = info.votes().unwrap();
+    let voters: Vec<_> = votes.iter().map(|vote| vote.voter()).collect();
+    assert_eq!(voters, ["whatlang", "always-ukrainian"]);
+    assert_eq!(votes[0].language(), Some("Russian"));
+    assert_eq!(votes[1].weight(), 3.0);
+
+    let info = detect_string("Триграммы - это частный случай n-грамм.");
+    assert!(info.info().unwrap().votes().is_none());
+
+    let e = DetectorConfig::new()
+        .backend("always-ukrainian")
+        .ensemble([("whatlang", 1.0)])
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+    let e = DetectorConfig::new()
+        .ensemble([("whatlang", -1.0)])
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+}
+
+#[test]
+fn trained_profiles_detect_custom_languages() {
+    let profiles = train_profiles(
+        vec![PathBuf::from("tests/dialects.jsonl")],
+        LabeledFormat::Jsonl,
+        "lang",
+        &TrainOptions::default(),
+    )
+    .unwrap();
+    let codes: Vec<_> = profiles.profiles().iter().map(|p| p.code()).collect();
+    assert_eq!(codes, ["deu", "gsw"]);
+    assert_eq!(profiles.profiles()[1].name(), "gsw");
+
+    let path = std::env::temp_dir().join(format!("whatlang-cli-lib-{}.json", std::process::id()));
+    profiles.save(&path).unwrap();
+    let detector = DetectorConfig::new()
+        .profiles([&path])
+        .allowlist(["deu", "gsw"])
+        .build()
+        .unwrap();
+    let result = detector.detect("Mir händ am Samschtig es Fescht gmacht und alli sind cho.");
+    assert_eq!(result.info().unwrap().code(), Some("gsw"));
+    let result = detector.detect("Wir haben am Samstag ein Fest gemacht und alle sind gekommen.");
+    assert_eq!(result.info().unwrap().code(), Some("deu"));
+
+    let e = DetectorConfig::new()
+        .profiles([path.with_extension("missing")])
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+
+    std::fs::write(
+        &path,
+        r#"{"profiles": [{"code": "../../escaped", "name": "Escaped", "script": "Latin", "examples": 1, "trigrams": {" a ": 1.0}}]}"#,
+    )
+    .unwrap();
+    let e = LanguageProfiles::load(&path).unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+    std::fs::write(
+        &path,
+        "{\"lang\": \"../../escaped\", \"text\": \"Hallo Welt\"}\n",
+    )
+    .unwrap();
+    let e = train_profiles(
+        vec![path.clone()],
+        LabeledFormat::Jsonl,
+        "lang",
+        &TrainOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn profiles_backend_detects_with_the_profiles_alone() {
+    let profiles = train_profiles(
+        vec![PathBuf::from("tests/dialects.jsonl")],
+        LabeledFormat::Jsonl,
+        "lang",
+        &TrainOptions::default(),
+    )
+    .unwrap();
+    let path =
+        std::env::temp_dir().join(format!("whatlang-cli-backend-{}.json", std::process::id()));
+    profiles.save(&path).unwrap();
+    assert!(backends().contains(&"profiles".to_string()));
+
+    let detector = DetectorConfig::new()
+        .backend("profiles")
+        .profiles([&path])
+        .build()
+        .unwrap();
+    let result = detector.detect("Mir händ am Samschtig es Fescht gmacht und alli sind cho.");
+    assert_eq!(result.info().unwrap().code(), Some("gsw"));
+
+    let e = DetectorConfig::new()
+        .backend("profiles")
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn script_composition_counts_scripts_and_kinds() {
+    let composition = script_composition("Мы смотрели Netflix, 2 часа! 🍿");
+    let scripts: Vec<_> = composition
+        .scripts()
+        .iter()
+        .map(|share| (share.script(), share.characters()))
+        .collect();
+    assert_eq!(scripts, [("Cyrillic", 14), ("Latin", 7)]);
+    assert_eq!(composition.scripts()[1].ratio(), 7.0 / 21.0);
+    assert_eq!(composition.characters(), 30);
+    assert_eq!(composition.digits(), 1);
+    assert_eq!(composition.punctuation(), 2);
+    assert_eq!(composition.emoji(), 1);
+    assert_eq!(script_composition("نعم، لا").punctuation(), 1);
+    assert_eq!(script_composition("हाँ। नहीं।").punctuation(), 2);
+    assert_eq!(script_composition("はい。「いいえ」").punctuation(), 3);
+
+    let detector = DetectorConfig::new().composition(true).build().unwrap();
+    let result = detector.detect("🍿🍿");
+    assert_eq!(result.error().unwrap().composition().unwrap().emoji(), 2);
+    assert!(detect_string("Netflix")
+        .info()
+        .unwrap()
+        .composition()
+        .is_none());
+}
+
+#[derive(Debug)]
+struct AlwaysUkrainian;
+
+impl LanguageDetector for AlwaysUkrainian {
+    fn detect(&self, _text: &str) -> Option<LangInfo> {
+        Some(LangInfo::new("Ukrainian", "Cyrillic", 1.0, true))
+    }
+}
+
+#[test]
+fn sampling_reads_only_part_of_large_files() {
+    let files = vec![PathBuf::from("tests/mixed.txt")];
+    let detect = |sampling: Sampling| {
+        DetectorConfig::new()
+            .sampling(Some(sampling))
+            .build()
+            .unwrap()
+            .detect_files(files.clone(), &FileOptions::default())
+            .unwrap()
+            .remove(0)
+    };
+
+    let result = detect(Sampling::new(200));
+    assert_eq!(result.results()[0].info().unwrap().language(), "German");
+    let sample = result.sample().unwrap();
+    assert_eq!(sample.strategy(), SampleStrategy::Head);
+    assert_eq!((sample.bytes(), sample.file_bytes()), (200, Some(709)));
+
+    let random = Sampling::new(300)
+        .strategy(SampleStrategy::Random)
+        .chunks(4)
+        .seed(42);
+    let result = detect(random.clone());
+    assert_eq!(result.sample().unwrap().chunks(), 4);
+    assert!(result.sample().unwrap().bytes() <= 300);
+    let again = detect(random);
+    assert_eq!(result.sample(), again.sample());
+    let confidence =
+        |result: &WhatLangFromFileResult| result.results()[0].info().unwrap().confidence();
+    assert_eq!(confidence(&result), confidence(&again));
+
+    assert!(detect(Sampling::new(1000)).sample().is_none());
+    let e = DetectorConfig::new()
+        .sampling(Some(Sampling::new(0)))
+        .build()
+        .unwrap_err();
+    assert_eq!(e.code(), "invalid_config");
+}
+
+#[test]
+fn detect_files_reads_mapped_and_empty_files() {
+    let dir = std::env::temp_dir();
+    let valid = dir.join(format!("whatlang-cli-lib-{}.txt", std::process::id()));
+    let empty = dir.join(format!("whatlang-cli-lib-{}-empty.txt", std::process::id()));
+    let invalid = dir.join(format!(
+        "whatlang-cli-lib-{}-invalid.txt",
+        std::process::id()
+    ));
+    let text = "Trigramme sind ein Spezialfall des n-Gramms, wobei n gleich 3 ist.";
+    std::fs::write(&valid, text).unwrap();
+    std::fs::write(&empty, "").unwrap();
+    std::fs::write(&invalid, b"abc\xff").unwrap();
+
+    let results = detect_files(
+        vec![invalid.clone(), valid.clone(), empty.clone()],
+        &FileOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[0].results()[0].info().unwrap().confidence(),
+        detect_string(text).info().unwrap().confidence()
+    );
+    assert_eq!(
+        results[1].results()[0].error().unwrap().code(),
+        "detection_failed"
+    );
+    for path in [valid, empty, invalid] {
+        std::fs::remove_file(path).unwrap();
+    }
+}